@@ -0,0 +1,201 @@
+use crate::error::{Error, Result};
+use crate::solutions::{Answer, Part};
+use crate::utils::{self, Dataset};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// The outcome of comparing a computed answer against the known one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    Pass,
+    Fail { expected: String },
+    Unknown,
+}
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::Pass => write!(f, "pass"),
+            Check::Fail { expected } => write!(f, "FAIL: expected {}", expected),
+            Check::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Known answers, keyed by day, part and dataset.
+///
+/// The answers file has one answer per line: `<day> <part> <dataset> <answer>`,
+/// e.g. `5 2 test 46`, where `<dataset>` is `real` or the name of an example.
+/// Blank lines and lines starting with `#` are ignored. Multi-line answers are
+/// written on one line with `\n` between the lines.
+#[derive(Debug, Default)]
+pub struct Answers(HashMap<(u32, Part, Dataset), String>);
+impl Answers {
+    pub fn load(path: impl AsRef<Path>) -> Result<Answers> {
+        Answers::parse(&utils::read_file_as_string(path)?)
+    }
+
+    pub fn parse(input: &str) -> Result<Answers> {
+        let entries = utils::parse_lines(input, parse_entry)?;
+        Ok(Answers(entries.into_iter().flatten().collect()))
+    }
+
+    /// Replaces the answers for real inputs with those in `other`.
+    pub fn replace_real(&mut self, other: Answers) {
+        self.0
+            .retain(|(_, _, dataset), _| *dataset != Dataset::Real);
+        self.0.extend(
+            other
+                .0
+                .into_iter()
+                .filter(|((_, _, dataset), _)| *dataset == Dataset::Real),
+        );
+    }
+
+    pub fn get(&self, day: u32, part: Part, dataset: &Dataset) -> Option<&str> {
+        self.0
+            .get(&(day, part, dataset.clone()))
+            .map(|s| s.as_str())
+    }
+
+    /// The examples that `part` of `day` should be checked against: those with
+    /// a known answer for that part, or the default example if there are none.
+    pub fn examples(&self, day: u32, part: Part) -> Vec<Dataset> {
+        let mut examples = self
+            .0
+            .keys()
+            .filter(|(d, p, dataset)| *d == day && *p == part && *dataset != Dataset::Real)
+            .map(|(_, _, dataset)| dataset.clone())
+            .collect::<Vec<Dataset>>();
+        if examples.is_empty() {
+            examples.push(Dataset::default_example());
+        }
+        examples.sort();
+        examples
+    }
+
+    pub fn check(&self, day: u32, part: Part, dataset: &Dataset, answer: &Answer) -> Check {
+        match self.get(day, part, dataset) {
+            None => Check::Unknown,
+            Some(expected) if expected == answer.to_string() => Check::Pass,
+            Some(expected) => Check::Fail {
+                expected: expected.to_string(),
+            },
+        }
+    }
+}
+
+type Entry = ((u32, Part, Dataset), String);
+
+fn parse_entry(line: &str) -> Result<Option<Entry>> {
+    if line.trim().is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let fields = line.splitn(4, ' ').collect::<Vec<&str>>();
+    if fields.len() != 4 {
+        return Err(Error::at(
+            line,
+            line,
+            "expected `<day> <part> <dataset> <answer>`",
+        ));
+    }
+    let day = utils::parse_number::<u32>(line, fields[0])?;
+    let part = match fields[1] {
+        "1" => Part::One,
+        "2" => Part::Two,
+        p => return Err(Error::at(line, p, "expected part `1` or `2`")),
+    };
+    let dataset = fields[2]
+        .parse::<Dataset>()
+        .map_err(|e| Error::at(line, fields[2], e))?;
+    Ok(Some(((day, part, dataset), fields[3].replace("\\n", "\n"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSWERS: &str = "\
+# day part dataset answer
+5 1 real 26273516
+5 2 test 46
+
+1 2 test2 281
+10 1 real #..#\\n.##.
+";
+
+    fn example(name: &str) -> Dataset {
+        Dataset::Example(name.to_string())
+    }
+
+    fn error(input: &str) -> String {
+        Answers::parse(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_entries() {
+        let answers = Answers::parse(ANSWERS).unwrap();
+        assert_eq!(answers.0.len(), 4);
+        assert_eq!(answers.get(5, Part::One, &Dataset::Real), Some("26273516"));
+        assert_eq!(answers.get(5, Part::Two, &example("test")), Some("46"));
+        assert_eq!(answers.get(5, Part::Two, &Dataset::Real), None);
+        assert_eq!(answers.get(1, Part::Two, &example("test2")), Some("281"));
+
+        assert_eq!(answers.examples(1, Part::Two), [example("test2")]);
+        assert_eq!(answers.examples(1, Part::One), [Dataset::default_example()]);
+    }
+
+    #[test]
+    fn multi_line_answers() {
+        let answers = Answers::parse(ANSWERS).unwrap();
+        assert_eq!(
+            answers.get(10, Part::One, &Dataset::Real),
+            Some("#..#\n.##.")
+        );
+        let drawn = Answer::Lines(Vec::from(["#..#".to_string(), ".##.".to_string()]));
+        assert_eq!(
+            answers.check(10, Part::One, &Dataset::Real, &drawn),
+            Check::Pass
+        );
+        let other = Answer::Lines(Vec::from(["#..#".to_string(), ".#.#".to_string()]));
+        assert_eq!(
+            answers.check(10, Part::One, &Dataset::Real, &other),
+            Check::Fail {
+                expected: "#..#\n.##.".to_string()
+            }
+        );
+        assert_eq!(
+            answers.check(10, Part::Two, &Dataset::Real, &drawn),
+            Check::Unknown
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_field() {
+        assert_eq!(
+            error("5 1 real 1\n5 1 real\n"),
+            "line 2, column 1: expected `<day> <part> <dataset> <answer>`"
+        );
+        assert_eq!(
+            error("# five\nfive 1 real 1\n"),
+            "line 2, column 1: invalid number `five`"
+        );
+        assert_eq!(
+            error("5 3 real 1\n"),
+            "line 1, column 3: expected part `1` or `2`"
+        );
+        assert_eq!(
+            error("\n\n5 1 re/al 1\n"),
+            "line 3, column 5: invalid dataset `re/al`: expected `real` or an example name made of letters, digits and `_`"
+        );
+    }
+
+    #[test]
+    fn replaces_only_real_answers() {
+        let mut answers = Answers::parse(ANSWERS).unwrap();
+        answers.replace_real(Answers::parse("5 2 real 34039469\n5 2 test 0\n").unwrap());
+        assert_eq!(answers.get(5, Part::One, &Dataset::Real), None);
+        assert_eq!(answers.get(5, Part::Two, &Dataset::Real), Some("34039469"));
+        assert_eq!(answers.get(5, Part::Two, &example("test")), Some("46"));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::config::{Config, SESSION_ENV};
+use crate::error::{Error, Result};
+use crate::http::HttpClient;
+use crate::utils::Dataset;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const BASE_URL: &str = "https://adventofcode.com";
+pub const YEAR: u32 = 2023;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fetched {
+    /// The input was already on disk, so nothing was downloaded.
+    Cached,
+    Downloaded,
+}
+
+/// Downloads the real input for `day` to where `config` expects to find it,
+/// unless it is already there. Returns where the input is.
+pub fn fetch_input(
+    client: &dyn HttpClient,
+    config: &Config,
+    day: u32,
+) -> Result<(Fetched, PathBuf)> {
+    let path = config.input_path(day, &Dataset::Real);
+    if is_cached(&path) {
+        return Ok((Fetched::Cached, path));
+    }
+
+    let url = format!("{}/{}/day/{}/input", config.base_url, YEAR, day);
+    let cookie = format!("session={}", session(config)?);
+    let response = client.get(&url, &[("Cookie", &cookie)])?;
+    if response.status != 200 {
+        return Err(Error::Http(format!(
+            "GET {}: status {}: {}",
+            url,
+            response.status,
+            response.body.trim()
+        )));
+    }
+
+    // Write to a temporary file first so an interrupted download is never
+    // mistaken for a cached input.
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    }
+    let partial = path.with_extension("data.partial");
+    fs::write(&partial, &response.body).map_err(|e| Error::io(&partial, e))?;
+    fs::rename(&partial, &path).map_err(|e| Error::io(&path, e))?;
+    Ok((Fetched::Downloaded, path))
+}
+
+fn is_cached(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() > 0)
+}
+
+pub(crate) fn session(config: &Config) -> Result<&str> {
+    match config.session.as_deref() {
+        Some(session) if !session.is_empty() => Ok(session),
+        _ => Err(Error::Config(format!(
+            "no session token: set {} or `session` in the config file",
+            SESSION_ENV
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::fake::{FakeClient, LocalServer};
+    use crate::http::UreqClient;
+    use crate::utils::TempDir;
+    use std::net::TcpListener;
+
+    fn config_in(dir: &TempDir) -> Config {
+        Config {
+            data_dir: dir.path().to_path_buf(),
+            user: Some("elf".to_string()),
+            session: Some("cookie".to_string()),
+            base_url: "http://aoc.test".to_string(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn downloads_then_uses_the_cached_input() {
+        let dir = TempDir::new("fetch");
+        let config = config_in(&dir);
+        let client = FakeClient::new([(200, "1abc2\n")]);
+        let (fetched, path) = fetch_input(&client, &config, 1).unwrap();
+        assert_eq!(fetched, Fetched::Downloaded);
+        assert_eq!(path, dir.path().join("users/elf/day1.data"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1abc2\n");
+        {
+            let requests = client.requests.borrow();
+            assert_eq!(requests.len(), 1);
+            assert_eq!(requests[0].method, "GET");
+            assert_eq!(requests[0].url, "http://aoc.test/2023/day/1/input");
+            assert_eq!(
+                requests[0].headers,
+                [("Cookie".to_string(), "session=cookie".to_string())]
+            );
+        }
+
+        // The client has no response left, so this would fail if it were asked.
+        assert_eq!(
+            fetch_input(&client, &config, 1).unwrap(),
+            (Fetched::Cached, path)
+        );
+        assert_eq!(client.requests.borrow().len(), 1);
+    }
+
+    #[test]
+    fn writes_nothing_on_an_error_status() {
+        let dir = TempDir::new("fetch");
+        let config = config_in(&dir);
+        let client = FakeClient::new([(404, "Not Found"), (500, "oops")]);
+        for _ in 0..2 {
+            let failed = fetch_input(&client, &config, 2);
+            assert!(matches!(failed, Err(Error::Http(_))));
+        }
+        let path = config.input_path(2, &Dataset::Real);
+        assert!(!path.exists());
+        assert!(!path.with_extension("data.partial").exists());
+        assert_eq!(client.requests.borrow().len(), 2);
+    }
+
+    #[test]
+    fn renames_the_partial_download_into_place() {
+        let dir = TempDir::new("fetch");
+        let config = config_in(&dir);
+        let path = config.input_path(3, &Dataset::Real);
+        let partial = path.with_extension("data.partial");
+        // Left over from an interrupted download, which is no cached input.
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&partial, "467..1").unwrap();
+        fs::write(&path, "").unwrap();
+
+        let client = FakeClient::new([(200, "467..114..\n")]);
+        let (fetched, _) = fetch_input(&client, &config, 3).unwrap();
+        assert_eq!(fetched, Fetched::Downloaded);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "467..114..\n");
+        assert!(!partial.exists());
+    }
+
+    #[test]
+    fn needs_a_session() {
+        let dir = TempDir::new("fetch");
+        let config = Config {
+            session: None,
+            ..config_in(&dir)
+        };
+        let client = FakeClient::new([]);
+        let failed = fetch_input(&client, &config, 4);
+        assert!(matches!(failed, Err(Error::Config(_))));
+        assert!(client.requests.borrow().is_empty());
+    }
+
+    #[test]
+    fn downloads_over_http() {
+        let dir = TempDir::new("fetch");
+        let server =
+            LocalServer::start([(200, "Time: 7\n"), (400, "Puzzle inputs differ by user.\n")]);
+        let config = Config {
+            base_url: server.url.clone(),
+            ..config_in(&dir)
+        };
+        let client = UreqClient::default();
+        let (fetched, path) = fetch_input(&client, &config, 6).unwrap();
+        assert_eq!(fetched, Fetched::Downloaded);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Time: 7\n");
+
+        let failed = fetch_input(&client, &config, 7);
+        assert!(
+            matches!(&failed, Err(Error::Http(m)) if *m == format!(
+                "GET {}/2023/day/7/input: status 400: Puzzle inputs differ by user.",
+                server.url
+            )),
+            "{:?}",
+            failed
+        );
+        assert!(!config.input_path(7, &Dataset::Real).exists());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        for (request, day) in requests.iter().zip([6, 7]) {
+            let line = format!("GET /2023/day/{}/input HTTP/1.1\r\n", day);
+            assert!(request.starts_with(&line), "{}", request);
+            assert!(
+                request.contains("\r\nCookie: session=cookie\r\n"),
+                "{}",
+                request
+            );
+            assert!(request.ends_with("\r\n\r\n"), "{}", request);
+        }
+    }
+
+    #[test]
+    fn unreachable_servers_are_http_errors() {
+        let dir = TempDir::new("fetch");
+        // A port that was free a moment ago, with nothing listening on it.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = Config {
+            base_url: format!("http://127.0.0.1:{}", port),
+            ..config_in(&dir)
+        };
+        let failed = fetch_input(&UreqClient::default(), &config, 8);
+        assert!(matches!(failed, Err(Error::Http(_))), "{:?}", failed);
+    }
+}
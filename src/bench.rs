@@ -0,0 +1,220 @@
+use crate::error::{Error, Result};
+use crate::solutions::{Day, Part};
+use crate::utils;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::hint;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    Parse,
+    PartOne,
+    PartTwo,
+}
+impl Phase {
+    fn of(part: Part) -> Phase {
+        match part {
+            Part::One => Phase::PartOne,
+            Part::Two => Phase::PartTwo,
+        }
+    }
+}
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Phase::Parse => write!(f, "parse"),
+            Phase::PartOne => write!(f, "part1"),
+            Phase::PartTwo => write!(f, "part2"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+}
+impl Stats {
+    fn from_samples(samples: &mut [Duration]) -> Stats {
+        samples.sort();
+        let total: Duration = samples.iter().sum();
+        Stats {
+            min: samples[0],
+            median: samples[samples.len() / 2],
+            mean: total / samples.len() as u32,
+        }
+    }
+}
+
+/// Times parsing and each of `parts` separately over `iterations` runs.
+pub fn run(
+    day: &Day,
+    input: &str,
+    parts: &[Part],
+    iterations: usize,
+) -> Result<Vec<(Phase, Stats)>> {
+    let iterations = iterations.max(1);
+    let mut phases = Vec::from([Phase::Parse]);
+    phases.extend(parts.iter().map(|&p| Phase::of(p)));
+    let mut samples = vec![Vec::with_capacity(iterations); phases.len()];
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let solution = day.parse(input)?;
+        samples[0].push(start.elapsed());
+
+        for (i, &part) in parts.iter().enumerate() {
+            let start = Instant::now();
+            hint::black_box(solution.solve(part));
+            samples[i + 1].push(start.elapsed());
+        }
+    }
+
+    Ok(phases
+        .into_iter()
+        .zip(samples.iter_mut())
+        .map(|(phase, samples)| (phase, Stats::from_samples(samples)))
+        .collect())
+}
+
+/// Median timings saved by an earlier run, keyed by day, dataset and phase.
+///
+/// The baseline file has one timing per line: `<day> <dataset> <phase> <nanoseconds>`.
+#[derive(Debug, Default)]
+pub struct Baseline(HashMap<(u32, String, Phase), Duration>);
+impl Baseline {
+    pub fn load(path: impl AsRef<Path>) -> Result<Baseline> {
+        let entries = utils::parse_lines(&utils::read_file_as_string(path)?, |line| {
+            let fields = line.split(' ').collect::<Vec<&str>>();
+            if fields.len() != 4 {
+                return Err(Error::at(
+                    line,
+                    line,
+                    "expected `<day> <dataset> <phase> <nanoseconds>`",
+                ));
+            }
+            let day = utils::parse_number::<u32>(line, fields[0])?;
+            let phase = match fields[2] {
+                "parse" => Phase::Parse,
+                "part1" => Phase::PartOne,
+                "part2" => Phase::PartTwo,
+                p => return Err(Error::at(line, p, "expected `parse`, `part1` or `part2`")),
+            };
+            let nanos = utils::parse_number::<u64>(line, fields[3])?;
+            Ok((
+                (day, fields[1].to_string(), phase),
+                Duration::from_nanos(nanos),
+            ))
+        })?;
+        Ok(Baseline(entries.into_iter().collect()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut entries = self.0.iter().collect::<Vec<_>>();
+        entries.sort();
+        let contents = entries
+            .iter()
+            .map(|((day, dataset, phase), median)| {
+                format!("{} {} {} {}\n", day, dataset, phase, median.as_nanos())
+            })
+            .collect::<String>();
+        fs::write(&path, contents).map_err(|e| Error::io(path, e))
+    }
+
+    pub fn get(&self, day: u32, dataset: &str, phase: Phase) -> Option<Duration> {
+        self.0.get(&(day, dataset.to_string(), phase)).copied()
+    }
+
+    pub fn insert(&mut self, day: u32, dataset: &str, phase: Phase, median: Duration) {
+        self.0.insert((day, dataset.to_string(), phase), median);
+    }
+}
+
+/// Describes how `median` compares to the baseline's median, e.g. `-12.5%`.
+pub fn change(median: Duration, baseline: Option<Duration>) -> String {
+    match baseline {
+        Some(baseline) if !baseline.is_zero() => {
+            let change = (median.as_secs_f64() / baseline.as_secs_f64() - 1.0) * 100.0;
+            format!("{:+.1}%", change)
+        }
+        _ => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TempDir;
+
+    fn load(contents: &str) -> Result<Baseline> {
+        let dir = TempDir::new("baseline");
+        let path = dir.path().join("bench_baseline.txt");
+        fs::write(&path, contents).unwrap();
+        Baseline::load(&path)
+    }
+
+    #[test]
+    fn loads_medians() {
+        let baseline = load("5 real parse 1500\n5 test2 part2 42\n").unwrap();
+        assert_eq!(
+            baseline.get(5, "real", Phase::Parse),
+            Some(Duration::from_nanos(1500))
+        );
+        assert_eq!(
+            baseline.get(5, "test2", Phase::PartTwo),
+            Some(Duration::from_nanos(42))
+        );
+        assert_eq!(baseline.get(5, "real", Phase::PartOne), None);
+        assert_eq!(baseline.get(6, "real", Phase::Parse), None);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let error = |contents| load(contents).unwrap_err().to_string();
+        assert_eq!(
+            error("5 real parse 1500\n5 real 1500\n"),
+            "line 2, column 1: expected `<day> <dataset> <phase> <nanoseconds>`"
+        );
+        assert_eq!(
+            error("5 real part3 1500\n"),
+            "line 1, column 8: expected `parse`, `part1` or `part2`"
+        );
+        assert_eq!(
+            error("5 real parse 1.5ms\n"),
+            "line 1, column 14: invalid number `1.5ms`"
+        );
+    }
+
+    #[test]
+    fn saves_and_loads_again() {
+        let mut baseline = Baseline::default();
+        baseline.insert(9, "real", Phase::PartTwo, Duration::from_micros(80));
+        baseline.insert(1, "test2", Phase::PartTwo, Duration::from_nanos(3));
+        baseline.insert(1, "real", Phase::Parse, Duration::from_millis(2));
+        baseline.insert(1, "real", Phase::Parse, Duration::from_millis(1));
+
+        let dir = TempDir::new("baseline");
+        let path = dir.path().join("bench_baseline.txt");
+        baseline.save(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "1 real parse 1000000\n1 test2 part2 3\n9 real part2 80000\n"
+        );
+        let loaded = Baseline::load(&path).unwrap();
+        assert_eq!(loaded.0, baseline.0);
+    }
+
+    #[test]
+    fn changes_against_the_baseline() {
+        let ms = Duration::from_millis;
+        assert_eq!(change(ms(150), Some(ms(100))), "+50.0%");
+        assert_eq!(change(ms(875), Some(ms(1000))), "-12.5%");
+        assert_eq!(change(ms(100), Some(ms(100))), "+0.0%");
+        assert_eq!(change(ms(100), Some(Duration::ZERO)), "-");
+        assert_eq!(change(ms(100), None), "-");
+    }
+}
@@ -0,0 +1,470 @@
+use crate::config::{self, Overrides};
+use crate::log::Filter;
+use crate::report::Format;
+use crate::solutions::Part;
+use crate::utils::{self, Dataset};
+use std::fmt;
+
+pub const USAGE: &str = "\
+Usage: advent2023 --day <DAYS> [OPTIONS]
+       advent2023 <COMMAND> --day <DAYS> [OPTIONS]
+
+Commands:
+  all                   Run every implemented day (or only DAYS) against its examples and
+                        real input, and print a table of answers, checks and timings.
+                        Runs on --jobs threads (default: one per CPU).
+  fetch                 Download the real inputs for DAYS, skipping any already downloaded.
+                        Needs a session token in ADVENT2023_SESSION or the config file.
+  new                   Start a single --day: create its module from a template, register it,
+                        and create an empty example, input and placeholder answers.
+                        Refuses to replace a day that already has a module.
+  watch                 Re-run a single --day against its examples and real input whenever
+                        its source, data files or known answers change, showing how the
+                        answers changed. Stop with Ctrl-C.
+  manifest              Record the SHA-1 digests of the data files of DAYS (default: all of
+                        them) in manifest.sha1 in each data directory.
+  verify                Check the data files of DAYS (default: all of them) against the
+                        manifest. Runs also refuse inputs that don't match it.
+  submit                Submit the answer to --part of a single --day, computing it from
+                        the real input unless --answer is given. Answers already known to
+                        be wrong, and answers sent before a rate limit runs out, are not
+                        sent; every attempt is recorded in <DIR>/submissions.txt.
+
+Options:
+  -d, --day <DAYS>      Day(s) to run, e.g. 5, 1,3,7 or 1-9. May be repeated.
+  -p, --part <PART>     Only run part 1 or part 2 (default: both).
+  -a, --answer <VALUE>  The answer to submit.
+  -t, --test            Run each part against its examples instead of the real input.
+  -e, --example <NAME>  Run against the example data/dayN_<NAME>.data only.
+  -i, --input <PATH>    Read the puzzle input from PATH, or from stdin if PATH is -. Input
+                        piped into a single day without --test is read like --input -.
+  -c, --check           Compare answers against the known answers and fail on a mismatch.
+  -b, --bench           Time parsing and each part instead of printing answers.
+  -n, --iterations <N>  Number of timed runs per day in bench mode (default: 20).
+  -f, --format <FORMAT> Write results as text (default), json, csv or tap, with timings and
+                        how each answer compares to the known one.
+  -j, --jobs <N>        Number of threads for `all`; timings are steadier with 1.
+      --save-baseline   Save the bench medians next to the real inputs to compare later runs.
+                        Not with --input.
+      --data-dir <DIR>  Read inputs and answers from DIR (default: the repo's data/).
+  -u, --user <NAME>     Use NAME's real inputs and answers from <DIR>/users/NAME/.
+      --config <PATH>   Read settings from PATH instead of advent2023.conf.
+      --log <FILTER>    Show debug output on stderr: a level (error, warn, info, debug or
+                        trace), or days and a level, e.g. 5:trace or 1-3:debug. May be
+                        repeated. Also read from ADVENT2023_LOG, separated by spaces.
+      --timeout <SECS>  Give up on parsing an input or solving a part after SECS seconds,
+                        e.g. 10 or 0.5, and report it as TIMEOUT. 0 is no limit (default).
+                        Also read from ADVENT2023_TIMEOUT or `timeout` in the config file.
+      --memory <MIB>    Stop parsing an input or solving a part when it tries to allocate
+                        more than MIB MiB, and report it as OOM. 0 is no limit (default).
+                        Also read from ADVENT2023_MEMORY or `memory` in the config file.
+  -h, --help            Print this help.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Stdin,
+    File(String),
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub days: Vec<u32>,
+    pub parts: Vec<Part>,
+    pub test_mode: bool,
+    pub example: Option<Dataset>,
+    pub input: Option<Input>,
+    pub check: bool,
+    pub bench: bool,
+    pub iterations: usize,
+    pub save_baseline: bool,
+    pub format: Format,
+    pub overrides: Overrides,
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Run(Options),
+    /// Every implemented day, or only `days` if any are given.
+    All {
+        days: Vec<u32>,
+        jobs: Option<usize>,
+        format: Format,
+        overrides: Overrides,
+    },
+    Fetch {
+        days: Vec<u32>,
+        overrides: Overrides,
+    },
+    New {
+        day: u32,
+        overrides: Overrides,
+    },
+    Watch {
+        day: u32,
+        overrides: Overrides,
+    },
+    /// Record the digests of the data files of `days`, or of all of them.
+    Manifest {
+        days: Vec<u32>,
+        overrides: Overrides,
+    },
+    /// Check the data files of `days`, or all of them, against the manifest.
+    Verify {
+        days: Vec<u32>,
+        overrides: Overrides,
+    },
+    Submit {
+        day: u32,
+        part: Part,
+        answer: Option<String>,
+        overrides: Overrides,
+    },
+    Help,
+}
+
+#[derive(Debug)]
+pub struct UsageError(pub String);
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, UsageError> {
+    let mut args = args.into_iter().peekable();
+    match args.peek().map(|arg| arg.as_str()) {
+        Some("all") => {
+            args.next();
+            parse_all(args)
+        }
+        Some("fetch") => {
+            args.next();
+            parse_days_only(args, |days, overrides| {
+                check_days(&days)?;
+                Ok(Command::Fetch { days, overrides })
+            })
+        }
+        Some("new") => {
+            args.next();
+            parse_days_only(args, |days, overrides| {
+                let day = single_day(&days, "new")?;
+                Ok(Command::New { day, overrides })
+            })
+        }
+        Some("watch") => {
+            args.next();
+            parse_days_only(args, |days, overrides| {
+                let day = single_day(&days, "watch")?;
+                Ok(Command::Watch { day, overrides })
+            })
+        }
+        Some("manifest") => {
+            args.next();
+            parse_days_only(args, |days, overrides| {
+                Ok(Command::Manifest { days, overrides })
+            })
+        }
+        Some("verify") => {
+            args.next();
+            parse_days_only(args, |days, overrides| {
+                Ok(Command::Verify { days, overrides })
+            })
+        }
+        Some("submit") => {
+            args.next();
+            parse_submit(args)
+        }
+        _ => parse_run(args),
+    }
+}
+
+fn parse_run(args: impl Iterator<Item = String>) -> Result<Command, UsageError> {
+    let mut days = Vec::new();
+    let mut parts = Vec::from([Part::One, Part::Two]);
+    let mut test_mode = false;
+    let mut example = None;
+    let mut input = None;
+    let mut check = false;
+    let mut bench = false;
+    let mut iterations = 20;
+    let mut save_baseline = false;
+    let mut format = Format::Text;
+    let mut overrides = Overrides::default();
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        if parse_shared(&arg, &mut args, &mut days, &mut overrides)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-p" | "--part" => parts = Vec::from([parse_part(&value_for(&arg, args.next())?)?]),
+            "-t" | "--test" => test_mode = true,
+            "-e" | "--example" => match value_for(&arg, args.next())?.parse::<Dataset>() {
+                Ok(Dataset::Real) => {
+                    return Err(UsageError("`real` is not an example name.".to_string()))
+                }
+                Ok(dataset) => {
+                    test_mode = true;
+                    example = Some(dataset);
+                }
+                Err(e) => return Err(UsageError(e)),
+            },
+            "-c" | "--check" => check = true,
+            "-b" | "--bench" => bench = true,
+            "-n" | "--iterations" => {
+                let value = value_for(&arg, args.next())?;
+                iterations = match value.parse::<usize>() {
+                    Ok(n) if n > 0 => n,
+                    _ => {
+                        return Err(UsageError(format!(
+                            "Invalid number of iterations: {}",
+                            value
+                        )))
+                    }
+                }
+            }
+            "--save-baseline" => save_baseline = true,
+            "-f" | "--format" => format = parse_format(&value_for(&arg, args.next())?)?,
+            "-i" | "--input" => {
+                input = match value_for(&arg, args.next())?.as_str() {
+                    "-" => Some(Input::Stdin),
+                    path => Some(Input::File(path.to_string())),
+                }
+            }
+            _ => return Err(UsageError(format!("Unexpected argument: {}", arg))),
+        }
+    }
+
+    check_days(&days)?;
+    if bench && format != Format::Text {
+        return Err(UsageError(
+            "--format cannot be used with --bench.".to_string(),
+        ));
+    }
+    if input.is_some() && days.len() > 1 {
+        return Err(UsageError(
+            "--input can only be used with a single day.".to_string(),
+        ));
+    }
+    if input.is_some() && save_baseline {
+        return Err(UsageError(
+            "--save-baseline cannot be used with --input: the baseline is for the data files."
+                .to_string(),
+        ));
+    }
+    Ok(Command::Run(Options {
+        days,
+        parts,
+        test_mode,
+        example,
+        input,
+        check,
+        bench,
+        iterations,
+        save_baseline,
+        format,
+        overrides,
+    }))
+}
+
+fn parse_all(mut args: impl Iterator<Item = String>) -> Result<Command, UsageError> {
+    let mut days = Vec::new();
+    let mut jobs = None;
+    let mut format = Format::Text;
+    let mut overrides = Overrides::default();
+    while let Some(arg) = args.next() {
+        if parse_shared(&arg, &mut args, &mut days, &mut overrides)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-f" | "--format" => format = parse_format(&value_for(&arg, args.next())?)?,
+            "-j" | "--jobs" => {
+                let value = value_for(&arg, args.next())?;
+                jobs = match value.parse::<usize>() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(UsageError(format!("Invalid number of jobs: {}", value))),
+                }
+            }
+            _ => return Err(UsageError(format!("Unexpected argument: {}", arg))),
+        }
+    }
+    Ok(Command::All {
+        days,
+        jobs,
+        format,
+        overrides,
+    })
+}
+
+/// Parses the arguments of a command that takes only days and the shared
+/// options, then makes the command from them with `make_command`.
+fn parse_days_only(
+    mut args: impl Iterator<Item = String>,
+    make_command: impl FnOnce(Vec<u32>, Overrides) -> Result<Command, UsageError>,
+) -> Result<Command, UsageError> {
+    let mut days = Vec::new();
+    let mut overrides = Overrides::default();
+    while let Some(arg) = args.next() {
+        if parse_shared(&arg, &mut args, &mut days, &mut overrides)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => return Err(UsageError(format!("Unexpected argument: {}", arg))),
+        }
+    }
+    make_command(days, overrides)
+}
+
+fn parse_submit(mut args: impl Iterator<Item = String>) -> Result<Command, UsageError> {
+    let mut days = Vec::new();
+    let mut part = None;
+    let mut answer = None;
+    let mut overrides = Overrides::default();
+    while let Some(arg) = args.next() {
+        if parse_shared(&arg, &mut args, &mut days, &mut overrides)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-p" | "--part" => part = Some(parse_part(&value_for(&arg, args.next())?)?),
+            "-a" | "--answer" => answer = Some(value_for(&arg, args.next())?),
+            _ => return Err(UsageError(format!("Unexpected argument: {}", arg))),
+        }
+    }
+    let day = single_day(&days, "submit")?;
+    let part = part.ok_or_else(|| UsageError("No part given. Use --part <PART>.".to_string()))?;
+    Ok(Command::Submit {
+        day,
+        part,
+        answer,
+        overrides,
+    })
+}
+
+/// Handles the options every command takes. Returns whether `arg` was one of them.
+fn parse_shared(
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+    days: &mut Vec<u32>,
+    overrides: &mut Overrides,
+) -> Result<bool, UsageError> {
+    match arg {
+        "-d" | "--day" => {
+            days.extend(utils::parse_days(&value_for(arg, args.next())?).map_err(UsageError)?)
+        }
+        "--data-dir" => overrides.data_dir = Some(value_for(arg, args.next())?),
+        "-u" | "--user" => overrides.user = Some(value_for(arg, args.next())?),
+        "--config" => overrides.config = Some(value_for(arg, args.next())?),
+        "--timeout" => {
+            let value = value_for(arg, args.next())?;
+            config::parse_timeout(&value).map_err(UsageError)?;
+            overrides.timeout = Some(value);
+        }
+        "--memory" => {
+            let value = value_for(arg, args.next())?;
+            config::parse_memory(&value).map_err(UsageError)?;
+            overrides.memory = Some(value);
+        }
+        "--log" => {
+            let directive = value_for(arg, args.next())?;
+            Filter::default().apply(&directive).map_err(UsageError)?;
+            overrides.log.push(directive);
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn check_days(days: &[u32]) -> Result<(), UsageError> {
+    match days.is_empty() {
+        true => Err(UsageError("No day given. Use --day <DAYS>.".to_string())),
+        false => Ok(()),
+    }
+}
+
+fn single_day(days: &[u32], command: &str) -> Result<u32, UsageError> {
+    check_days(days)?;
+    match days {
+        [day] => Ok(*day),
+        _ => Err(UsageError(format!("{} takes a single day.", command))),
+    }
+}
+
+fn value_for(flag: &str, value: Option<String>) -> Result<String, UsageError> {
+    value.ok_or_else(|| UsageError(format!("Missing value for {}", flag)))
+}
+
+fn parse_format(s: &str) -> Result<Format, UsageError> {
+    s.parse::<Format>().map_err(UsageError)
+}
+
+fn parse_part(s: &str) -> Result<Part, UsageError> {
+    match s {
+        "1" => Ok(Part::One),
+        "2" => Ok(Part::Two),
+        _ => Err(UsageError(format!(
+            "Invalid part: {}. Only 1 and 2 are supported.",
+            s
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Command, UsageError> {
+        parse_args(args.split_whitespace().map(String::from))
+    }
+
+    fn error(args: &str) -> String {
+        match parse(args) {
+            Err(UsageError(message)) => message,
+            Ok(command) => panic!("`{}` parsed as {:?}", args, command),
+        }
+    }
+
+    #[test]
+    fn commands_taking_only_days() {
+        assert!(matches!(
+            parse("fetch --day 1-3 --user elf"),
+            Ok(Command::Fetch { days, overrides }) if days == [1, 2, 3] && overrides.user.as_deref() == Some("elf")
+        ));
+        assert!(matches!(
+            parse("new --day 10"),
+            Ok(Command::New { day: 10, .. })
+        ));
+        assert!(matches!(
+            parse("watch -d 4"),
+            Ok(Command::Watch { day: 4, .. })
+        ));
+        assert!(matches!(parse("manifest"), Ok(Command::Manifest { days, .. }) if days.is_empty()));
+        assert!(
+            matches!(parse("verify --day 2,5"), Ok(Command::Verify { days, .. }) if days == [2, 5])
+        );
+        assert!(matches!(parse("verify --help"), Ok(Command::Help)));
+
+        assert_eq!(error("fetch"), "No day given. Use --day <DAYS>.");
+        assert!(error("new --day 1,2").contains("single day"));
+        assert!(error("watch").contains("No day given"));
+        assert_eq!(error("manifest --part 1"), "Unexpected argument: --part");
+    }
+
+    #[test]
+    fn baselines_are_only_saved_for_data_files() {
+        assert!(matches!(
+            parse("--day 5 --bench --save-baseline"),
+            Ok(Command::Run(options)) if options.save_baseline && options.input.is_none()
+        ));
+        for input in ["other.data", "-"] {
+            let args = format!("--day 5 --bench --input {} --save-baseline", input);
+            assert_eq!(
+                error(&args),
+                "--save-baseline cannot be used with --input: the baseline is for the data files."
+            );
+        }
+    }
+}
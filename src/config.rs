@@ -0,0 +1,422 @@
+use crate::aoc;
+use crate::error::{Error, Result};
+use crate::log::Filter;
+use crate::runner::Limits;
+use crate::utils::{self, Dataset};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const CONFIG_FILE: &str = "advent2023.conf";
+pub const CONFIG_ENV: &str = "ADVENT2023_CONFIG";
+pub const DATA_DIR_ENV: &str = "ADVENT2023_DATA_DIR";
+pub const USER_ENV: &str = "ADVENT2023_USER";
+pub const SESSION_ENV: &str = "ADVENT2023_SESSION";
+pub const BASE_URL_ENV: &str = "ADVENT2023_BASE_URL";
+pub const LOG_ENV: &str = "ADVENT2023_LOG";
+pub const TIMEOUT_ENV: &str = "ADVENT2023_TIMEOUT";
+pub const MEMORY_ENV: &str = "ADVENT2023_MEMORY";
+
+/// Settings given on the command line. They take precedence over the
+/// environment, which takes precedence over the config file.
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub config: Option<String>,
+    pub data_dir: Option<String>,
+    pub user: Option<String>,
+    /// `--log` directives, applied after those in the environment.
+    pub log: Vec<String>,
+    pub timeout: Option<String>,
+    pub memory: Option<String>,
+}
+
+/// Where puzzle inputs and answers live.
+///
+/// Examples and their answers are shared and always come from `data_dir`. The
+/// real input differs per person: by default it is `data_dir/dayN.data`, and
+/// with a user set it is `data_dir/users/<user>/dayN.data`, with that user's
+/// answers in `data_dir/users/<user>/answers.txt`.
+///
+/// `session` is the adventofcode.com session cookie used to download inputs,
+/// and `base_url` the site to download them from. `limits` bound how long
+/// and with how much memory each parse and part may run.
+#[derive(Debug)]
+pub struct Config {
+    pub data_dir: PathBuf,
+    pub user: Option<String>,
+    pub session: Option<String>,
+    pub base_url: String,
+    pub limits: Limits,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            data_dir: Path::new(env!("CARGO_MANIFEST_DIR")).join("data"),
+            user: None,
+            session: None,
+            base_url: aoc::BASE_URL.to_string(),
+            limits: Limits::default(),
+        }
+    }
+}
+impl Config {
+    pub fn load(overrides: &Overrides) -> Result<Config> {
+        Config::load_with(overrides, |name| env::var(name).ok())
+    }
+
+    /// Loads the config with `var` standing in for the environment.
+    fn load_with(overrides: &Overrides, var: impl Fn(&str) -> Option<String>) -> Result<Config> {
+        let mut config = Config::default();
+        if let Some(path) = config_file(overrides, &var) {
+            config.apply_file(&path)?;
+        }
+        if let Some(data_dir) = var(DATA_DIR_ENV) {
+            config.data_dir = PathBuf::from(data_dir);
+        }
+        if let Some(user) = var(USER_ENV) {
+            config.user = Some(user);
+        }
+        if let Some(session) = var(SESSION_ENV) {
+            config.session = Some(session.trim().to_string());
+        }
+        if let Some(base_url) = var(BASE_URL_ENV) {
+            config.base_url = base_url;
+        }
+        if let Some(timeout) = var(TIMEOUT_ENV) {
+            config.limits.timeout = parse_timeout(&timeout)
+                .map_err(|e| Error::Config(format!("{}: {}", TIMEOUT_ENV, e)))?;
+        }
+        if let Some(memory) = var(MEMORY_ENV) {
+            config.limits.memory = parse_memory(&memory)
+                .map_err(|e| Error::Config(format!("{}: {}", MEMORY_ENV, e)))?;
+        }
+        if let Some(data_dir) = &overrides.data_dir {
+            config.data_dir = PathBuf::from(data_dir);
+        }
+        if let Some(user) = &overrides.user {
+            config.user = Some(user.clone());
+        }
+        if let Some(timeout) = &overrides.timeout {
+            config.limits.timeout = parse_timeout(timeout).map_err(Error::Config)?;
+        }
+        if let Some(memory) = &overrides.memory {
+            config.limits.memory = parse_memory(memory).map_err(Error::Config)?;
+        }
+
+        if let Some(user) = &config.user {
+            if user.is_empty()
+                || !user
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(Error::Config(format!(
+                    "invalid user `{}`: only letters, digits, `_` and `-` are allowed",
+                    user
+                )));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Reads `key = value` lines from a config file. Relative paths are
+    /// resolved against the directory the file is in.
+    fn apply_file(&mut self, path: &Path) -> Result<()> {
+        let contents = utils::read_file_as_string(path)?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid =
+                |message: &str| Error::Config(format!("{}:{}: {}", path.display(), i + 1, message));
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("expected `<key> = <value>`"))?;
+            let value = value.trim();
+            match key.trim() {
+                "data_dir" => self.data_dir = base.join(value),
+                "user" => self.user = Some(value.to_string()),
+                "session" => self.session = Some(value.to_string()),
+                "base_url" => self.base_url = value.to_string(),
+                "timeout" => self.limits.timeout = parse_timeout(value).map_err(|e| invalid(&e))?,
+                "memory" => self.limits.memory = parse_memory(value).map_err(|e| invalid(&e))?,
+                key => return Err(invalid(&format!("unknown key `{}`", key))),
+            }
+        }
+        Ok(())
+    }
+
+    /// The directory holding the real inputs and their answers.
+    pub fn input_dir(&self) -> PathBuf {
+        match &self.user {
+            Some(user) => self.data_dir.join("users").join(user),
+            None => self.data_dir.clone(),
+        }
+    }
+
+    /// The directories holding data files: the shared one, and the user's if
+    /// set.
+    pub fn data_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = Vec::from([self.data_dir.clone()]);
+        if self.input_dir() != self.data_dir {
+            dirs.push(self.input_dir());
+        }
+        dirs
+    }
+
+    pub fn input_path(&self, day: u32, dataset: &Dataset) -> PathBuf {
+        match dataset {
+            Dataset::Real => self.input_dir().join(dataset.file_name(day)),
+            Dataset::Example(_) => self.data_dir.join(dataset.file_name(day)),
+        }
+    }
+
+    pub fn answers_path(&self) -> PathBuf {
+        self.data_dir.join("answers.txt")
+    }
+
+    /// The bench medians saved with `--save-baseline`, kept next to the real
+    /// inputs they were timed on.
+    pub fn baseline_path(&self) -> PathBuf {
+        self.input_dir().join("bench_baseline.txt")
+    }
+
+    /// The answers for the user's real inputs, if a user is set.
+    pub fn user_answers_path(&self) -> Option<PathBuf> {
+        self.user
+            .as_ref()
+            .map(|_| self.input_dir().join("answers.txt"))
+    }
+}
+
+/// Parses a time limit in seconds, e.g. `10` or `0.5`. `0` is no limit.
+pub fn parse_timeout(s: &str) -> std::result::Result<Option<Duration>, String> {
+    match s.parse::<f64>() {
+        Ok(0.0) => Ok(None),
+        Ok(secs) if secs.is_finite() && secs > 0.0 => Ok(Some(Duration::from_secs_f64(secs))),
+        _ => Err(format!(
+            "invalid timeout `{}`: expected a number of seconds",
+            s
+        )),
+    }
+}
+
+/// Parses a memory limit in MiB. `0` is no limit.
+pub fn parse_memory(s: &str) -> std::result::Result<Option<usize>, String> {
+    match s
+        .parse::<usize>()
+        .ok()
+        .and_then(|mib| mib.checked_mul(1024 * 1024))
+    {
+        Some(0) => Ok(None),
+        Some(bytes) => Ok(Some(bytes)),
+        None => Err(format!(
+            "invalid memory limit `{}`: expected a number of MiB",
+            s
+        )),
+    }
+}
+
+/// Which log messages to show: none by default, then the whitespace-separated
+/// directives in the environment, then those given with `--log`.
+pub fn log_filter(overrides: &Overrides) -> Result<Filter> {
+    let mut filter = Filter::default();
+    let from_env = env::var(LOG_ENV).unwrap_or_default();
+    for directive in from_env.split_whitespace() {
+        filter
+            .apply(directive)
+            .map_err(|e| Error::Config(format!("{}: {}", LOG_ENV, e)))?;
+    }
+    for directive in &overrides.log {
+        filter.apply(directive).map_err(Error::Config)?;
+    }
+    Ok(filter)
+}
+
+/// The config file to read: the one given with `--config` or in the
+/// environment, or else `advent2023.conf` in the current directory or the
+/// repository root, if there is one.
+fn config_file(overrides: &Overrides, var: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    if let Some(path) = &overrides.config {
+        return Some(PathBuf::from(path));
+    }
+    if let Some(path) = var(CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    [
+        PathBuf::from(CONFIG_FILE),
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(CONFIG_FILE),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TempDir;
+    use std::collections::HashMap;
+    use std::fs;
+
+    /// Writes a config file into `dir` and returns overrides that read it.
+    fn with_file(dir: &TempDir, contents: &str) -> Overrides {
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(&path, contents).unwrap();
+        Overrides {
+            config: Some(path.display().to_string()),
+            ..Overrides::default()
+        }
+    }
+
+    fn load(overrides: &Overrides, vars: &[(&str, &str)]) -> Result<Config> {
+        let vars = vars.iter().copied().collect::<HashMap<&str, &str>>();
+        Config::load_with(overrides, |name| vars.get(name).map(|v| v.to_string()))
+    }
+
+    fn config_error(result: Result<Config>) -> String {
+        match result {
+            Err(Error::Config(message)) => message,
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn command_line_then_environment_then_file() {
+        let dir = TempDir::new("config");
+        let file = "\
+# shared settings
+data_dir = inputs
+user = from_file
+session = file-cookie
+base_url = http://aoc.test
+timeout = 1
+memory = 1
+";
+        let config = load(&with_file(&dir, file), &[]).unwrap();
+        assert_eq!(config.data_dir, dir.path().join("inputs"));
+        assert_eq!(config.user.as_deref(), Some("from_file"));
+        assert_eq!(config.session.as_deref(), Some("file-cookie"));
+        assert_eq!(config.base_url, "http://aoc.test");
+        assert_eq!(
+            config.limits,
+            Limits {
+                timeout: Some(Duration::from_secs(1)),
+                memory: Some(1024 * 1024),
+            }
+        );
+
+        let vars = [
+            (USER_ENV, "from-env"),
+            (SESSION_ENV, " env-cookie\n"),
+            (TIMEOUT_ENV, "2"),
+            (DATA_DIR_ENV, "/srv/advent"),
+        ];
+        let config = load(&with_file(&dir, file), &vars).unwrap();
+        assert_eq!(config.data_dir, PathBuf::from("/srv/advent"));
+        assert_eq!(config.user.as_deref(), Some("from-env"));
+        assert_eq!(config.session.as_deref(), Some("env-cookie"));
+        assert_eq!(config.limits.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(config.limits.memory, Some(1024 * 1024));
+
+        let overrides = Overrides {
+            user: Some("cli".to_string()),
+            timeout: Some("0".to_string()),
+            data_dir: Some("data".to_string()),
+            ..with_file(&dir, file)
+        };
+        let config = load(&overrides, &vars).unwrap();
+        assert_eq!(config.data_dir, PathBuf::from("data"));
+        assert_eq!(config.user.as_deref(), Some("cli"));
+        assert_eq!(config.limits.timeout, None);
+        assert_eq!(config.base_url, "http://aoc.test");
+    }
+
+    #[test]
+    fn config_file_from_the_environment() {
+        let dir = TempDir::new("config");
+        let given = with_file(&dir, "user = given\n");
+        let other = dir.path().join("other.conf");
+        fs::write(&other, "user = other\ndata_dir = /srv/advent\n").unwrap();
+        let vars = [(CONFIG_ENV, other.to_str().unwrap())];
+
+        let config = load(&Overrides::default(), &vars).unwrap();
+        assert_eq!(config.user.as_deref(), Some("other"));
+        assert_eq!(config.data_dir, PathBuf::from("/srv/advent"));
+        let config = load(&given, &vars).unwrap();
+        assert_eq!(config.user.as_deref(), Some("given"));
+    }
+
+    #[test]
+    fn invalid_files() {
+        let dir = TempDir::new("config");
+        let path = dir.path().join(CONFIG_FILE).display().to_string();
+        let message = config_error(load(&with_file(&dir, "user = elf\n\ncolour = red\n"), &[]));
+        assert_eq!(message, format!("{}:3: unknown key `colour`", path));
+        let message = config_error(load(&with_file(&dir, "user elf\n"), &[]));
+        assert_eq!(message, format!("{}:1: expected `<key> = <value>`", path));
+        let message = config_error(load(&with_file(&dir, "timeout = soon\n"), &[]));
+        assert_eq!(
+            message,
+            format!(
+                "{}:1: invalid timeout `soon`: expected a number of seconds",
+                path
+            )
+        );
+
+        let missing = Overrides {
+            config: Some(dir.path().join("missing.conf").display().to_string()),
+            ..Overrides::default()
+        };
+        assert!(matches!(load(&missing, &[]), Err(Error::Io { .. })));
+    }
+
+    #[test]
+    fn invalid_environment() {
+        let dir = TempDir::new("config");
+        let overrides = with_file(&dir, "");
+        let message = config_error(load(&overrides, &[(MEMORY_ENV, "lots")]));
+        assert_eq!(
+            message,
+            "ADVENT2023_MEMORY: invalid memory limit `lots`: expected a number of MiB"
+        );
+    }
+
+    #[test]
+    fn user_names() {
+        let dir = TempDir::new("config");
+        for user in ["elf", "Elf_2", "snow-maker"] {
+            let config = load(&with_file(&dir, ""), &[(USER_ENV, user)]).unwrap();
+            assert_eq!(config.user.as_deref(), Some(user));
+        }
+        for user in ["", "../elf", "elf elf", "elf/day1"] {
+            let message = config_error(load(&with_file(&dir, ""), &[(USER_ENV, user)]));
+            assert_eq!(
+                message,
+                format!(
+                    "invalid user `{}`: only letters, digits, `_` and `-` are allowed",
+                    user
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn timeouts() {
+        assert_eq!(parse_timeout("0"), Ok(None));
+        assert_eq!(parse_timeout("10"), Ok(Some(Duration::from_secs(10))));
+        assert_eq!(parse_timeout("0.5"), Ok(Some(Duration::from_millis(500))));
+        for invalid in ["", "-1", "inf", "NaN", "1s"] {
+            assert!(parse_timeout(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn memory_limits() {
+        assert_eq!(parse_memory("0"), Ok(None));
+        assert_eq!(parse_memory("64"), Ok(Some(64 * 1024 * 1024)));
+        for invalid in ["", "-1", "1.5", "64M", &usize::MAX.to_string()] {
+            assert!(parse_memory(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::utils;
+use std::path::Path;
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    /// Malformed puzzle input. Lines and columns are 1-based.
+    Parse {
+        day: Option<u32>,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// An invalid setting in a config file or the environment.
+    Config(String),
+    /// A failed request to adventofcode.com.
+    Http(String),
+    /// An action that was not taken because it would waste a submission or
+    /// overwrite existing work.
+    Refused(String),
+    /// A data file whose contents don't match the digest recorded for it.
+    Integrity(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn io(path: impl AsRef<Path>, source: io::Error) -> Error {
+        Error::Io {
+            path: path.as_ref().display().to_string(),
+            source,
+        }
+    }
+
+    pub fn parse(line: usize, column: usize, message: impl Into<String>) -> Error {
+        Error::Parse {
+            day: None,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Builds a parse error pointing at `token`, which must be a slice of `text`.
+    pub fn at(text: &str, token: &str, message: impl Into<String>) -> Error {
+        let (line, column) = utils::position_of(text, token);
+        Error::parse(line, column, message)
+    }
+
+    /// Shifts the line of a parse error by `lines`, for errors raised while
+    /// parsing a piece of a larger input.
+    pub fn offset_lines(self, lines: usize) -> Error {
+        match self {
+            Error::Parse {
+                day,
+                line,
+                column,
+                message,
+            } => Error::Parse {
+                day,
+                line: line + lines,
+                column,
+                message,
+            },
+            e => e,
+        }
+    }
+
+    pub fn in_day(self, day: u32) -> Error {
+        match self {
+            Error::Parse {
+                line,
+                column,
+                message,
+                ..
+            } => Error::Parse {
+                day: Some(day),
+                line,
+                column,
+                message,
+            },
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::Parse {
+                day,
+                line,
+                column,
+                message,
+            } => {
+                if let Some(day) = day {
+                    write!(f, "day {}, ", day)?;
+                }
+                write!(f, "line {}, column {}: {}", line, column, message)
+            }
+            Error::Config(message)
+            | Error::Http(message)
+            | Error::Refused(message)
+            | Error::Integrity(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse { .. }
+            | Error::Config(_)
+            | Error::Http(_)
+            | Error::Refused(_)
+            | Error::Integrity(_) => None,
+        }
+    }
+}
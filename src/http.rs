@@ -0,0 +1,219 @@
+use crate::error::{Error, Result};
+use std::io::Read;
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+/// The HTTP requests the Advent of Code client needs, so that tests can talk
+/// to a local stand-in server instead of adventofcode.com.
+pub trait HttpClient {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response>;
+    /// Sends `form` as an `application/x-www-form-urlencoded` POST body.
+    fn post_form(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        form: &[(&str, &str)],
+    ) -> Result<Response>;
+}
+
+/// The HTTP client used against the real site.
+pub struct UreqClient {
+    agent: ureq::Agent,
+}
+impl Default for UreqClient {
+    fn default() -> Self {
+        UreqClient {
+            agent: ureq::AgentBuilder::new()
+                .user_agent("github.com/shardulbee/advent2023")
+                .build(),
+        }
+    }
+}
+impl UreqClient {
+    fn send(
+        &self,
+        url: &str,
+        result: std::result::Result<ureq::Response, ureq::Error>,
+    ) -> Result<Response> {
+        let response = match result {
+            Ok(response) => response,
+            // Error statuses still carry a body worth showing.
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(Error::Http(format!("{}: {}", url, e))),
+        };
+        let status = response.status();
+        let mut body = String::new();
+        response
+            .into_reader()
+            .read_to_string(&mut body)
+            .map_err(|e| Error::Http(format!("{}: {}", url, e)))?;
+        Ok(Response { status, body })
+    }
+}
+impl HttpClient for UreqClient {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response> {
+        let mut request = self.agent.get(url);
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        self.send(url, request.call())
+    }
+
+    fn post_form(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        form: &[(&str, &str)],
+    ) -> Result<Response> {
+        let mut request = self.agent.post(url);
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        self.send(url, request.send_form(form))
+    }
+}
+
+/// A stand-in for the site, for tests.
+#[cfg(test)]
+pub(crate) mod fake {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread::{self, JoinHandle};
+
+    /// A request sent to a `FakeClient`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Request {
+        pub method: &'static str,
+        pub url: String,
+        pub headers: Vec<(String, String)>,
+        pub form: Vec<(String, String)>,
+    }
+
+    /// A client that answers from a queue of canned responses and records
+    /// every request it is sent.
+    #[derive(Default)]
+    pub struct FakeClient {
+        responses: RefCell<VecDeque<Response>>,
+        pub requests: RefCell<Vec<Request>>,
+    }
+    impl FakeClient {
+        /// A client that will answer with each of `responses` in turn.
+        pub fn new(responses: impl IntoIterator<Item = (u16, &'static str)>) -> FakeClient {
+            let responses = responses
+                .into_iter()
+                .map(|(status, body)| Response {
+                    status,
+                    body: body.to_string(),
+                })
+                .collect();
+            FakeClient {
+                responses: RefCell::new(responses),
+                requests: RefCell::default(),
+            }
+        }
+
+        fn answer(
+            &self,
+            method: &'static str,
+            url: &str,
+            headers: &[(&str, &str)],
+            form: &[(&str, &str)],
+        ) -> Result<Response> {
+            let owned = |pairs: &[(&str, &str)]| {
+                pairs
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect()
+            };
+            self.requests.borrow_mut().push(Request {
+                method,
+                url: url.to_string(),
+                headers: owned(headers),
+                form: owned(form),
+            });
+            self.responses
+                .borrow_mut()
+                .pop_front()
+                .ok_or_else(|| Error::Http(format!("{} {}: no response left", method, url)))
+        }
+    }
+    impl HttpClient for FakeClient {
+        fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response> {
+            self.answer("GET", url, headers, &[])
+        }
+
+        fn post_form(
+            &self,
+            url: &str,
+            headers: &[(&str, &str)],
+            form: &[(&str, &str)],
+        ) -> Result<Response> {
+            self.answer("POST", url, headers, form)
+        }
+    }
+
+    /// A server on a local port that answers one connection with each of its
+    /// canned responses in turn, for testing `UreqClient` over a real socket.
+    pub struct LocalServer {
+        /// The base URL to send requests to, like `http://127.0.0.1:40123`.
+        pub url: String,
+        handle: JoinHandle<Vec<String>>,
+    }
+    impl LocalServer {
+        pub fn start(responses: impl IntoIterator<Item = (u16, &'static str)>) -> LocalServer {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("could not bind a local port");
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let responses = responses.into_iter().collect::<Vec<_>>();
+            let handle = thread::spawn(move || {
+                let mut requests = Vec::new();
+                for (status, body) in responses {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    requests.push(read_request(&mut stream));
+                    write!(
+                        stream,
+                        "HTTP/1.1 {} Canned\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    )
+                    .unwrap();
+                }
+                requests
+            });
+            LocalServer { url, handle }
+        }
+
+        /// Waits until every response has been sent, and returns the requests
+        /// that were answered, exactly as they were received.
+        pub fn requests(self) -> Vec<String> {
+            self.handle.join().expect("the local server failed")
+        }
+    }
+
+    /// Reads a request's head, and then its body if it has one.
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut reader = BufReader::new(stream);
+        let mut request = String::new();
+        while !request.ends_with("\r\n\r\n") {
+            if reader.read_line(&mut request).unwrap() == 0 {
+                return request;
+            }
+        }
+        let length = request.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().unwrap())
+        });
+        let mut body = vec![0; length.unwrap_or(0)];
+        reader.read_exact(&mut body).unwrap();
+        request.push_str(&String::from_utf8(body).unwrap());
+        request
+    }
+}
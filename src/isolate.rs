@@ -0,0 +1,443 @@
+//! Parsing and solving a part in a child process, so that a part which runs
+//! out of time can be killed and one which runs out of memory fails on its own.
+//!
+//! The child is this same program, or a fresh build of it for `watch`,
+//! started as `advent2023 __solve <day> <part>` with the input on stdin. It reports back on stdout: first a line
+//! `parse <nanos>`, or `error` or `panic` followed by the message; then a line
+//! `solve <nanos> <kind>` followed by the answer, or `panic` followed by the
+//! message. Anything it logs goes to its stderr, which is passed on.
+
+use crate::config::{self, Overrides};
+use crate::error::Error;
+use crate::log;
+use crate::runner::{self, Limits, Status};
+use crate::solutions::{self, Answer, Part};
+use crate::utils;
+use std::env;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, ExitCode, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The hidden command that runs a child.
+pub const COMMAND: &str = "__solve";
+
+/// What stderr says when an allocation fails.
+const ALLOCATION_FAILED: &str = "memory allocation of";
+
+/// Parses `input` as `day`'s input and solves `part` in a child process
+/// within `limits`. Returns the answer, or the status to record instead, along
+/// with how long parsing and solving took.
+pub fn solve(
+    day: u32,
+    part: Part,
+    input: &str,
+    limits: &Limits,
+) -> (Result<Answer, Status>, Duration, Duration) {
+    match env::current_exe() {
+        Ok(program) => solve_with(&program, day, part, input, limits),
+        Err(e) => {
+            let status = Status::Error(format!("could not find this program: {}", e));
+            (Err(status), Duration::ZERO, Duration::ZERO)
+        }
+    }
+}
+
+/// Like `solve`, but with `program`, another build of this one, as the child.
+pub fn solve_with(
+    program: &Path,
+    day: u32,
+    part: Part,
+    input: &str,
+    limits: &Limits,
+) -> (Result<Answer, Status>, Duration, Duration) {
+    let mut child = match spawn(program, day, part, limits) {
+        Ok(child) => child,
+        Err(e) => {
+            let status = Status::Error(format!("could not start a child process: {}", e));
+            return (Err(status), Duration::ZERO, Duration::ZERO);
+        }
+    };
+    // Fed and read on threads of their own, so that a child that never reads
+    // its input or fills its output can't block us.
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_string();
+        thread::spawn(move || stdin.write_all(input.as_bytes()));
+    }
+    let oom = child.stderr.take().map(pass_on);
+    let lines = child.stdout.take().map(read_reports);
+
+    let (outcome, parse_time, solve_time) = match lines {
+        Some(lines) => watch(&mut child, &lines, limits),
+        None => (Err(None), Duration::ZERO, Duration::ZERO),
+    };
+    let _ = child.kill();
+    let exit = child.wait();
+    let out_of_memory = oom.is_some_and(|oom| oom.join().unwrap_or(false));
+    let outcome = outcome.map_err(|status| match (status, out_of_memory) {
+        (Some(status), _) => status,
+        (None, true) => Status::OutOfMemory(format!(
+            "tried to use more than {} MiB",
+            limits.memory.unwrap_or(0) / (1024 * 1024)
+        )),
+        (None, false) => Status::Panicked(match exit {
+            Ok(exit) => format!("the child process stopped with {}", exit),
+            Err(e) => format!("the child process was lost: {}", e),
+        }),
+    });
+    (outcome, parse_time, solve_time)
+}
+
+fn spawn(program: &Path, day: u32, part: Part, limits: &Limits) -> io::Result<Child> {
+    let mut command = Command::new(program);
+    command
+        .args([COMMAND, &day.to_string(), &part.number().to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(filter) = log::filter() {
+        command.env(config::LOG_ENV, filter.to_string());
+    }
+    if let Some(bytes) = limits.memory {
+        limit_memory(&mut command, bytes);
+    }
+    command.spawn()
+}
+
+/// Caps the data segment, i.e. the heap, of the process `command` starts.
+#[cfg(unix)]
+fn limit_memory(command: &mut Command, bytes: usize) {
+    use std::os::unix::process::CommandExt;
+
+    let limit = libc::rlimit {
+        rlim_cur: bytes as libc::rlim_t,
+        rlim_max: bytes as libc::rlim_t,
+    };
+    // SAFETY: `setrlimit` is async-signal-safe and nothing else runs between
+    // the fork and the exec.
+    unsafe {
+        command.pre_exec(move || match libc::setrlimit(libc::RLIMIT_DATA, &limit) {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        });
+    }
+}
+
+/// Memory limits are only enforced on unix.
+#[cfg(not(unix))]
+fn limit_memory(_command: &mut Command, _bytes: usize) {}
+
+/// Copies the child's stderr to ours as it comes, up to a failed allocation,
+/// after which it only has the noise of the child aborting. Ends with whether
+/// an allocation failed.
+fn pass_on(stderr: impl Read + Send + 'static) -> thread::JoinHandle<bool> {
+    thread::spawn(move || {
+        let mut out_of_memory = false;
+        for line in BufReader::new(stderr).lines() {
+            let Ok(line) = line else { break };
+            out_of_memory |= line.starts_with(ALLOCATION_FAILED);
+            if !out_of_memory {
+                eprintln!("{}", line);
+            }
+        }
+        out_of_memory
+    })
+}
+
+/// A line the child reported, with everything after it if the line was a
+/// failure or an answer. `None` once the child's output ends.
+type Report = Option<(String, String)>;
+
+/// Reads the child's reports as they come: a line, then for a failure or an
+/// answer, everything after it.
+fn read_reports(stdout: impl Read + Send + 'static) -> Receiver<Report> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdout = BufReader::new(stdout);
+        loop {
+            let mut line = String::new();
+            if !matches!(stdout.read_line(&mut line), Ok(n) if n > 0) {
+                let _ = sender.send(None);
+                return;
+            }
+            let line = line.trim_end().to_string();
+            let mut rest = String::new();
+            // Only a successful parse is followed by another report.
+            if !line.starts_with("parse ") {
+                let _ = stdout.read_to_string(&mut rest);
+            }
+            if sender.send(Some((line, rest))).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+/// Waits for the child's reports, giving parsing and then solving up to the
+/// timeout each. A child that stops without reporting ends with no status,
+/// for the caller to explain.
+fn watch(
+    child: &mut Child,
+    reports: &Receiver<Report>,
+    limits: &Limits,
+) -> (Result<Answer, Option<Status>>, Duration, Duration) {
+    let mut parse_time = None;
+    let mut started = Instant::now();
+    // Times so far, with the phase that is running at `elapsed`.
+    let times = |parse_time: Option<Duration>, elapsed: Duration| match parse_time {
+        None => (elapsed, Duration::ZERO),
+        Some(parse_time) => (parse_time, elapsed),
+    };
+    loop {
+        let report = match limits.timeout {
+            Some(timeout) => {
+                match reports.recv_timeout(timeout.saturating_sub(started.elapsed())) {
+                    Ok(report) => report,
+                    Err(RecvTimeoutError::Timeout) => {
+                        let _ = child.kill();
+                        let status = Status::Timeout(format!("gave up after {:.2?}", timeout));
+                        let (parse_time, solve_time) = times(parse_time, started.elapsed());
+                        return (Err(Some(status)), parse_time, solve_time);
+                    }
+                    Err(RecvTimeoutError::Disconnected) => None,
+                }
+            }
+            None => reports.recv().unwrap_or(None),
+        };
+        let (parse_elapsed, solve_elapsed) = times(parse_time, started.elapsed());
+        let Some((line, rest)) = report else {
+            return (Err(None), parse_elapsed, solve_elapsed);
+        };
+        match decode(&line, rest) {
+            Decoded::Parsed(time) if parse_time.is_none() => {
+                parse_time = Some(time);
+                started = Instant::now();
+            }
+            Decoded::Parsed(_) => {
+                let status = Status::Error(format!("unreadable report from the child: `{}`", line));
+                return (Err(Some(status)), parse_elapsed, solve_elapsed);
+            }
+            Decoded::Solved(answer, time) => return (Ok(answer), parse_elapsed, time),
+            Decoded::Failed(status) => return (Err(Some(status)), parse_elapsed, solve_elapsed),
+        }
+    }
+}
+
+/// What one of the child's reports says.
+#[derive(Debug, PartialEq, Eq)]
+enum Decoded {
+    /// Parsing took this long.
+    Parsed(Duration),
+    Solved(Answer, Duration),
+    /// Parsing or solving failed, or the report made no sense.
+    Failed(Status),
+}
+
+/// Reads a report: its first `line`, and for a failure or an answer the
+/// `rest` that followed it.
+fn decode(line: &str, rest: String) -> Decoded {
+    let mut words = line.split(' ');
+    match (words.next(), words.next(), words.next()) {
+        (Some("parse"), Some(nanos), None) => Decoded::Parsed(nanos_to_duration(nanos)),
+        (Some("error"), None, None) => Decoded::Failed(Status::Error(rest)),
+        (Some("panic"), None, None) => Decoded::Failed(Status::Panicked(rest)),
+        (Some("solve"), Some(nanos), Some(kind)) => {
+            let answer = match kind {
+                "number" => rest.parse::<i64>().map(Answer::Number).ok(),
+                "text" => Some(Answer::Text(rest)),
+                "lines" => Some(Answer::Lines(rest.lines().map(str::to_string).collect())),
+                _ => None,
+            };
+            match answer {
+                Some(answer) => Decoded::Solved(answer, nanos_to_duration(nanos)),
+                None => Decoded::Failed(Status::Error(format!(
+                    "unreadable answer from the child: `{}`",
+                    line
+                ))),
+            }
+        }
+        _ => Decoded::Failed(Status::Error(format!(
+            "unreadable report from the child: `{}`",
+            line
+        ))),
+    }
+}
+
+/// Writes the report of a failed parse or solve: `kind` is `error` or
+/// `panic`.
+fn encode_failure(out: &mut impl Write, kind: &str, message: &str) -> io::Result<()> {
+    write!(out, "{}\n{}", kind, message)
+}
+
+fn encode_answer(out: &mut impl Write, time: Duration, answer: &Answer) -> io::Result<()> {
+    let nanos = time.as_nanos();
+    match answer {
+        Answer::Number(n) => write!(out, "solve {} number\n{}", nanos, n),
+        Answer::Text(text) => write!(out, "solve {} text\n{}", nanos, text),
+        Answer::Lines(lines) => write!(out, "solve {} lines\n{}", nanos, lines.join("\n")),
+    }
+}
+
+fn nanos_to_duration(nanos: &str) -> Duration {
+    Duration::from_nanos(nanos.parse().unwrap_or(0))
+}
+
+/// Runs as the child: `args` are the day and part, and the input is on stdin.
+pub fn serve(args: &[String]) -> ExitCode {
+    let (day, part) = match args {
+        [day, part] => (
+            day.parse::<u32>().ok(),
+            match part.as_str() {
+                "1" => Some(Part::One),
+                "2" => Some(Part::Two),
+                _ => None,
+            },
+        ),
+        _ => (None, None),
+    };
+    let (Some(day), Some(part)) = (day, part) else {
+        eprintln!("usage: advent2023 {} <day> <part>", COMMAND);
+        return ExitCode::from(2);
+    };
+    // Reported rather than refused, since a child built from a newer or older
+    // source may not have the day its parent asks for.
+    let mut stdout = io::stdout();
+    let Some(day) = solutions::get(day) else {
+        let message = format!("day {} is not implemented yet", day);
+        let _ = encode_failure(&mut stdout, "error", &message);
+        return ExitCode::FAILURE;
+    };
+    if let Ok(filter) = config::log_filter(&Overrides::default()) {
+        log::init(filter);
+    }
+    runner::capture_panics();
+    let input = match utils::read_stdin_as_string() {
+        Ok(input) => input,
+        // Reading grows its buffer fallibly, so going over the limit here is
+        // an error rather than an abort. Reported like the abort would be.
+        Err(Error::Io { source, .. }) if source.kind() == io::ErrorKind::OutOfMemory => {
+            eprintln!("{} the input failed", ALLOCATION_FAILED);
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            let _ = encode_failure(&mut stdout, "error", &e.to_string());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let start = Instant::now();
+    let solution = match runner::catch(|| day.parse(&input)) {
+        Ok(Ok(solution)) => solution,
+        Ok(Err(e)) => {
+            let _ = encode_failure(&mut stdout, "error", &e.to_string());
+            return ExitCode::FAILURE;
+        }
+        Err(message) => {
+            let _ = encode_failure(&mut stdout, "panic", &message);
+            return ExitCode::FAILURE;
+        }
+    };
+    let _ = writeln!(stdout, "parse {}", start.elapsed().as_nanos());
+    let _ = stdout.flush();
+
+    let start = Instant::now();
+    match runner::catch(|| solution.solve(part)) {
+        Ok(answer) => {
+            let _ = encode_answer(&mut stdout, start.elapsed(), &answer);
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            let _ = encode_failure(&mut stdout, "panic", &message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Decodes everything `written` says, the way the parent reads a child.
+    fn read_back(written: Vec<u8>) -> Vec<Decoded> {
+        let reports = read_reports(Cursor::new(written));
+        let mut decoded = Vec::new();
+        while let Ok(Some((line, rest))) = reports.recv() {
+            decoded.push(decode(&line, rest));
+        }
+        decoded
+    }
+
+    fn parsed_then(report: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Vec<Decoded> {
+        let mut written = Vec::new();
+        writeln!(written, "parse {}", 1500).unwrap();
+        report(&mut written).unwrap();
+        read_back(written)
+    }
+
+    #[test]
+    fn answers_round_trip() {
+        let time = Duration::from_nanos(42);
+        for answer in [
+            Answer::Number(-1930746032),
+            Answer::Text("VHZ, or not".to_string()),
+            Answer::Lines(Vec::from([
+                "#..#".to_string(),
+                "".to_string(),
+                ".##.".to_string(),
+            ])),
+        ] {
+            let decoded = parsed_then(|out| encode_answer(out, time, &answer));
+            assert_eq!(
+                decoded,
+                [
+                    Decoded::Parsed(Duration::from_nanos(1500)),
+                    Decoded::Solved(answer, time)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn failures_round_trip() {
+        let message = "attempt to subtract with overflow\nat src/solutions/day_nine.rs:34:5";
+        let decoded = parsed_then(|out| encode_failure(out, "panic", message));
+        assert_eq!(
+            decoded[1],
+            Decoded::Failed(Status::Panicked(message.to_string()))
+        );
+
+        let mut written = Vec::new();
+        encode_failure(
+            &mut written,
+            "error",
+            "line 1, column 5: expected an integer",
+        )
+        .unwrap();
+        assert_eq!(
+            read_back(written),
+            [Decoded::Failed(Status::Error(
+                "line 1, column 5: expected an integer".to_string()
+            ))]
+        );
+    }
+
+    #[test]
+    fn unreadable_reports() {
+        let unreadable = |line: &str| match decode(line, "7".to_string()) {
+            Decoded::Failed(Status::Error(message)) => message,
+            other => panic!("`{}` decoded as {:?}", line, other),
+        };
+        assert!(unreadable("hello").starts_with("unreadable report"));
+        assert!(unreadable("parse").starts_with("unreadable report"));
+        assert!(unreadable("solve 5 colour").starts_with("unreadable answer"));
+        assert!(matches!(
+            decode("solve 5 number", "seven".to_string()),
+            Decoded::Failed(Status::Error(_))
+        ));
+        assert!(read_back(Vec::new()).is_empty());
+    }
+}
@@ -1,2 +1,17 @@
+pub mod answers;
+pub mod aoc;
+pub mod bench;
+pub mod cli;
+pub mod config;
+pub mod error;
+pub mod http;
+pub mod isolate;
+pub mod log;
+pub mod manifest;
+pub mod report;
+pub mod runner;
+pub mod scaffold;
 pub mod solutions;
+pub mod submit;
 pub mod utils;
+pub mod watch;
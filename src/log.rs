@@ -0,0 +1,233 @@
+//! Leveled debug output for solutions, silent unless switched on.
+//!
+//! Messages go to stderr so they never mix with answers. Each message is
+//! attributed to the day whose module logged it, which lets the output be
+//! switched on for some days only, e.g. `--log 5:trace`.
+
+use crate::solutions;
+use crate::utils;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!(
+                "invalid log level `{}`: expected error, warn, info, debug or trace",
+                s
+            )),
+        }
+    }
+}
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        })
+    }
+}
+
+/// The most detailed level shown, overall and for particular days.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub default: Option<Level>,
+    pub days: HashMap<u32, Level>,
+}
+impl Filter {
+    /// Applies a directive: a level, which sets the default, or days and a
+    /// level, e.g. `4:debug` or `1-3,7:trace`.
+    pub fn apply(&mut self, directive: &str) -> Result<(), String> {
+        match directive.rsplit_once(':') {
+            Some((days, level)) => {
+                let level = level.parse::<Level>()?;
+                for day in utils::parse_days(days)? {
+                    self.days.insert(day, level);
+                }
+            }
+            None => self.default = Some(directive.parse::<Level>()?),
+        }
+        Ok(())
+    }
+
+    /// The most detailed level shown for any day, if anything is shown.
+    fn most_detailed(&self) -> Option<Level> {
+        self.days.values().copied().chain(self.default).max()
+    }
+
+    fn enabled(&self, level: Level, day: Option<u32>) -> bool {
+        let max = day
+            .and_then(|day| self.days.get(&day).copied())
+            .or(self.default);
+        max.is_some_and(|max| level <= max)
+    }
+}
+
+/// The directives that make up the filter, e.g. `info 5:trace`.
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut directives = Vec::new();
+        if let Some(level) = self.default {
+            directives.push(level.to_string());
+        }
+        let mut days = self.days.iter().collect::<Vec<_>>();
+        days.sort();
+        for (day, level) in days {
+            directives.push(format!("{}:{}", day, level));
+        }
+        write!(f, "{}", directives.join(" "))
+    }
+}
+
+static FILTER: OnceLock<Filter> = OnceLock::new();
+/// The filter's most detailed level, so that messages no day shows are
+/// dismissed without working out their day.
+static MOST_DETAILED: OnceLock<Option<Level>> = OnceLock::new();
+
+/// Switches logging on. Only the first call has any effect.
+pub fn init(filter: Filter) {
+    let most_detailed = filter.most_detailed();
+    if FILTER.set(filter).is_ok() {
+        let _ = MOST_DETAILED.set(most_detailed);
+    }
+}
+
+/// The filter logging was switched on with, if it was.
+pub fn filter() -> Option<&'static Filter> {
+    FILTER.get()
+}
+
+/// The day whose solution module is `module`, e.g. 4 for
+/// `advent2023::solutions::day_four`.
+fn day_of(module: &str) -> Option<u32> {
+    static DAYS: OnceLock<HashMap<String, u32>> = OnceLock::new();
+    let days = DAYS.get_or_init(|| {
+        (1..=25)
+            .map(|day| (solutions::module_name(day), day))
+            .collect()
+    });
+    let name = module.rsplit("::").find(|name| name.starts_with("day_"))?;
+    days.get(name).copied()
+}
+
+/// Whether a message at `level` from `module` would be shown, and the day it
+/// is from.
+fn check(level: Level, module: &str) -> Option<Option<u32>> {
+    let filter = FILTER.get()?;
+    let most_detailed = MOST_DETAILED.get().copied().flatten();
+    if most_detailed.is_none_or(|max| level > max) {
+        return None;
+    }
+    let day = day_of(module);
+    filter.enabled(level, day).then_some(day)
+}
+
+pub fn enabled(level: Level, module: &str) -> bool {
+    check(level, module).is_some()
+}
+
+/// Writes a message; called through the logging macros.
+pub fn log(level: Level, module: &str, args: fmt::Arguments) {
+    let Some(day) = check(level, module) else {
+        return;
+    };
+    match day {
+        Some(day) => eprintln!("[{:<5} day {}] {}", level, day, args),
+        None => eprintln!("[{:<5} {}] {}", level, module, args),
+    }
+}
+
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        $crate::log::log($level, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => { $crate::log!($crate::log::Level::Error, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => { $crate::log!($crate::log::Level::Warn, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::log!($crate::log::Level::Info, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::log!($crate::log::Level::Debug, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => { $crate::log!($crate::log::Level::Trace, $($arg)+) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(directives: &[&str]) -> Filter {
+        let mut filter = Filter::default();
+        for directive in directives {
+            filter.apply(directive).unwrap();
+        }
+        filter
+    }
+
+    #[test]
+    fn filters_by_day() {
+        let filter = filter(&["info", "1-3,7:trace", "2:warn"]);
+        assert!(filter.enabled(Level::Info, None));
+        assert!(!filter.enabled(Level::Debug, Some(5)));
+        assert!(filter.enabled(Level::Trace, Some(7)));
+        assert!(!filter.enabled(Level::Info, Some(2)));
+        assert_eq!(filter.most_detailed(), Some(Level::Trace));
+        assert_eq!(filter.to_string(), "INFO 1:TRACE 2:WARN 3:TRACE 7:TRACE");
+
+        let quiet = Filter::default();
+        assert!(!quiet.enabled(Level::Error, Some(1)));
+        assert_eq!(quiet.most_detailed(), None);
+        assert_eq!(
+            self::filter(&["4:debug"]).most_detailed(),
+            Some(Level::Debug)
+        );
+        assert!(Filter::default().apply("5:loud").is_err());
+        assert!(Filter::default().apply("26:info").is_err());
+    }
+
+    #[test]
+    fn days_of_modules() {
+        assert_eq!(day_of("advent2023::solutions::day_four"), Some(4));
+        assert_eq!(day_of("advent2023::solutions::day_twenty_five"), Some(25));
+        assert_eq!(day_of("advent2023::solutions::day_five::tests"), Some(5));
+        assert_eq!(day_of("advent2023::runner"), None);
+        assert_eq!(day_of("advent2023::solutions::day_zero"), None);
+    }
+}
@@ -1,38 +1,624 @@
-use advent2023::solutions::*;
-use std::io::{self, Write};
-
-fn main() {
-    let mut day_input = String::new();
-    let mut test_input = String::new();
-
-    println!("Which day do you want to run? ");
-    print!("Input: ");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut day_input).unwrap();
-    let day_input = day_input.trim();
-
-    println!("Testmode? Enter Y/N.");
-    print!("Input: ");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut test_input).unwrap();
-    let test_input = test_input.trim();
-
-    let test_mode = match test_input {
-        "Y" => true,
-        "N" => false,
-        _ => panic!("Invalid input!. Only Y/N are supported."),
-    };
-
-    match day_input {
-        "1" => day_one::run(test_mode),
-        "2" => day_two::run(test_mode),
-        "3" => day_three::run(test_mode),
-        "4" => day_four::run(test_mode),
-        "5" => day_five::run(test_mode),
-        "6" => day_six::run(test_mode),
-        "7" => day_seven::run(test_mode),
-        "8" => day_eight::run(test_mode),
-        "9" => day_nine::run(test_mode),
-        _ => println!("Not implemented yet"),
+use advent2023::answers::{Answers, Check};
+use advent2023::aoc::{self, Fetched};
+use advent2023::bench::{self, Baseline};
+use advent2023::cli::{self, Command, Input, Options, USAGE};
+use advent2023::config::{self, Config, Overrides};
+use advent2023::error::{Error, Result};
+use advent2023::http::UreqClient;
+use advent2023::isolate;
+use advent2023::log;
+use advent2023::manifest::{self, State};
+use advent2023::report::{self, Format};
+use advent2023::runner::{self, Limits, Record, Run, Status};
+use advent2023::scaffold::{self, Change};
+use advent2023::solutions::{self, Part};
+use advent2023::submit::{self as submission, Outcome};
+use advent2023::utils::{self, Dataset};
+use advent2023::watch::{self, Watcher};
+use std::env;
+use std::path::Path;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Instant;
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if args.first().map(String::as_str) == Some(isolate::COMMAND) {
+        return isolate::serve(&args[1..]);
+    }
+    let mut options = match cli::parse_args(args) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::All {
+            days,
+            jobs,
+            format,
+            overrides,
+        }) => return all(&days, jobs, format, &overrides),
+        Ok(Command::Fetch { days, overrides }) => return fetch(&days, &overrides),
+        Ok(Command::New { day, overrides }) => return new(day, &overrides),
+        Ok(Command::Watch { day, overrides }) => return watch(day, &overrides),
+        Ok(Command::Manifest { days, overrides }) => return record_manifest(&days, &overrides),
+        Ok(Command::Verify { days, overrides }) => return verify(&days, &overrides),
+        Ok(Command::Submit {
+            day,
+            part,
+            answer,
+            overrides,
+        }) => return submit(day, part, answer, &overrides),
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    // Input piped into a run of the real input of a single day stands in for
+    // that input, as if given with `--input -`. Say so, since a shell loop's
+    // stdin is piped too. Saving a baseline always times the data file.
+    if options.input.is_none()
+        && !options.test_mode
+        && !options.save_baseline
+        && options.days.len() == 1
+        && utils::stdin_is_piped()
+    {
+        eprintln!(
+            "note: reading day {}'s input from stdin; redirect stdin from /dev/null to use its data file",
+            options.days[0]
+        );
+        options.input = Some(Input::Stdin);
+    }
+
+    let Some(config) = load_config(&options.overrides) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(answers) = load_answers(&options, &config) else {
+        return ExitCode::FAILURE;
+    };
+    let ok = match options.bench {
+        true => bench(&options, &config, &answers),
+        false => run(&options, &config, &answers),
+    };
+    match ok {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// Loads the settings and switches on any logging they ask for.
+fn load_config(overrides: &Overrides) -> Option<Config> {
+    let config = config::log_filter(overrides).and_then(|filter| {
+        log::init(filter);
+        Config::load(overrides)
+    });
+    match config {
+        Ok(config) => Some(config),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            None
+        }
+    }
+}
+
+fn all(days: &[u32], jobs: Option<usize>, format: Format, overrides: &Overrides) -> ExitCode {
+    let Some(config) = load_config(overrides) else {
+        return ExitCode::FAILURE;
+    };
+    let answers = match known_answers(&config) {
+        Ok(answers) => answers,
+        Err(e) => {
+            eprintln!("error: could not load known answers: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let days = match days.is_empty() {
+        true => solutions::DAYS.iter().collect::<Vec<_>>(),
+        false => {
+            let mut found = Vec::new();
+            for &day in days {
+                match solutions::get(day) {
+                    Some(solution) => found.push(solution),
+                    None => eprintln!("warning: day {} is not implemented yet", day),
+                }
+            }
+            found
+        }
+    };
+
+    runner::capture_panics();
+    let start = Instant::now();
+    let jobs = jobs.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+    let runs = runner::plan(&days, &answers);
+    let read = |run: &Run| read_input(run.day.number, run.dataset.as_ref(), None, &config);
+    let records = report(format, &runs, read, &answers, &config.limits, jobs);
+    print!("{}", report::end(format, &records, start.elapsed()));
+    // Other formats carry error messages in the records themselves.
+    if format == Format::Text {
+        for record in &records {
+            if let Some(message) = record.message() {
+                eprintln!(
+                    "error: day {} ({}) part {}: {}",
+                    record.day,
+                    record.dataset_name(),
+                    record.part.number(),
+                    message
+                );
+            }
+        }
+    }
+    let ok = records
+        .iter()
+        .all(|r| matches!(r.status, Status::Pass | Status::Unknown));
+    match ok {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// Executes `runs`, writing each record in `format` as soon as it is ready.
+fn report(
+    format: Format,
+    runs: &[Run],
+    read: impl Fn(&Run) -> Result<String> + Sync,
+    answers: &Answers,
+    limits: &Limits,
+    jobs: usize,
+) -> Vec<Record> {
+    let total = runs.iter().map(|run| run.parts.len()).sum();
+    print!("{}", report::begin(format, total));
+    let mut index = 0;
+    runner::execute_all(runs, read, answers, limits, jobs, |record| {
+        print!("{}", report::row(format, index, record));
+        index += 1;
+    })
+}
+
+fn fetch(days: &[u32], overrides: &Overrides) -> ExitCode {
+    let Some(config) = load_config(overrides) else {
+        return ExitCode::FAILURE;
+    };
+    let client = UreqClient::default();
+    let mut ok = true;
+    for &day in days {
+        match aoc::fetch_input(&client, &config, day) {
+            Ok((Fetched::Cached, path)) => println!("Day {}: already have {}", day, path.display()),
+            Ok((Fetched::Downloaded, path)) => println!("Day {}: saved {}", day, path.display()),
+            Err(e) => {
+                eprintln!("error: day {}: {}", day, e);
+                ok = false;
+            }
+        }
+    }
+    match ok {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+fn new(day: u32, overrides: &Overrides) -> ExitCode {
+    let Some(config) = load_config(overrides) else {
+        return ExitCode::FAILURE;
+    };
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let changes = match scaffold::scaffold(root, &config, day) {
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    for (change, path) in changes {
+        let change = match change {
+            Change::Created => "created",
+            Change::Updated => "updated",
+            Change::Kept => "kept",
+        };
+        println!("{:<8} {}", change, path.display());
+    }
+    println!(
+        "\nPaste the example into {}, then `cargo run -- --day {} --test`.",
+        config
+            .input_path(day, &Dataset::default_example())
+            .display(),
+        day
+    );
+    ExitCode::SUCCESS
+}
+
+fn watch(day: u32, overrides: &Overrides) -> ExitCode {
+    let Some(config) = load_config(overrides) else {
+        return ExitCode::FAILURE;
+    };
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Found before the first rebuild replaces the running binary.
+    let program = match env::current_exe() {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("error: could not find this program: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut watcher = Watcher::new(root, &config, day);
+    let mut previous = None;
+    let mut first = true;
+    loop {
+        let changed = watcher.changed();
+        if !changed.is_empty() {
+            match first {
+                true => println!("Watching day {}. Stop with Ctrl-C.", day),
+                false => println!(
+                    "\nChanged: {}",
+                    changed
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+            first = false;
+            // Reloaded every time, since the answers may be what changed.
+            let results = known_answers(&config)
+                .and_then(|answers| watch::run_day(root, &program, &config, &answers, day));
+            match results {
+                Ok(Some(results)) => {
+                    for line in watch::describe(previous.as_ref(), &results) {
+                        println!("{}", line);
+                    }
+                    previous = Some(results);
+                }
+                Ok(None) => println!("Build failed, waiting for changes."),
+                Err(e) => eprintln!("error: {}", e),
+            }
+        }
+        thread::sleep(watch::POLL_INTERVAL);
+    }
+}
+
+fn record_manifest(days: &[u32], overrides: &Overrides) -> ExitCode {
+    let Some(config) = load_config(overrides) else {
+        return ExitCode::FAILURE;
+    };
+    let mut ok = true;
+    for dir in config.data_dirs() {
+        let states = match manifest::record(&dir, days) {
+            Ok(states) => states,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ok = false;
+                continue;
+            }
+        };
+        for (name, state) in states {
+            let change = match state {
+                State::Ok => continue,
+                State::Changed { .. } => "updated",
+                State::Unlisted => "added",
+                State::Missing => "removed",
+            };
+            println!("{:<8} {}", change, dir.join(name).display());
+        }
+        println!("Recorded {}", manifest::Manifest::path(&dir).display());
+    }
+    match ok {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+fn verify(days: &[u32], overrides: &Overrides) -> ExitCode {
+    let Some(config) = load_config(overrides) else {
+        return ExitCode::FAILURE;
+    };
+    let mut ok = true;
+    for dir in config.data_dirs() {
+        let states = match manifest::verify(&dir, days) {
+            Ok(states) => states,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ok = false;
+                continue;
+            }
+        };
+        for (name, state) in states {
+            let path = dir.join(name);
+            match &state {
+                State::Changed { expected, actual } => println!(
+                    "{:<8} {}: expected sha1 {}, found {}",
+                    state,
+                    path.display(),
+                    expected,
+                    actual
+                ),
+                _ => println!("{:<8} {}", state, path.display()),
+            }
+            // A file that was never recorded may simply be new.
+            ok &= matches!(state, State::Ok | State::Unlisted);
+        }
+    }
+    match ok {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+fn submit(day: u32, part: Part, answer: Option<String>, overrides: &Overrides) -> ExitCode {
+    let Some(config) = load_config(overrides) else {
+        return ExitCode::FAILURE;
+    };
+    let answer = match answer {
+        Some(answer) => answer,
+        None => {
+            let Some(solution) = solutions::get(day) else {
+                eprintln!("error: day {} is not implemented yet", day);
+                return ExitCode::FAILURE;
+            };
+            let input = manifest::read_checked(config.input_path(day, &Dataset::Real));
+            match input.and_then(|input| solution.solve(&input, part)) {
+                Ok(answer) => answer.to_string(),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    };
+
+    let name = match part {
+        Part::One => "one",
+        Part::Two => "two",
+    };
+    println!("Day {} part {}: submitting {}", day, name, answer);
+    let attempt = match submission::submit(&UreqClient::default(), &config, day, part, &answer) {
+        Ok(attempt) => attempt,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("{}", attempt.outcome);
+    if !attempt.wait.is_zero() {
+        println!(
+            "Wait {}s before sending another answer.",
+            attempt.wait.as_secs()
+        );
+    }
+    match attempt.outcome {
+        Outcome::Correct => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}
+
+/// The shared answers, with the user's answers for real inputs if a user is set.
+fn known_answers(config: &Config) -> Result<Answers> {
+    let mut answers = Answers::load(config.answers_path())?;
+    if let Some(path) = config.user_answers_path() {
+        answers.replace_real(match Answers::load(path) {
+            Ok(user_answers) => user_answers,
+            Err(Error::Io { .. }) => Answers::default(),
+            Err(e) => return Err(e),
+        });
+    }
+    Ok(answers)
+}
+
+fn load_answers(options: &Options, config: &Config) -> Option<Answers> {
+    // The answers file also says which examples belong to which part, so it
+    // is needed in test mode even when answers aren't being checked.
+    match known_answers(config) {
+        Ok(answers) => Some(answers),
+        Err(e) if options.check => {
+            eprintln!("error: could not load known answers: {}", e);
+            None
+        }
+        Err(e) => {
+            if options.test_mode {
+                eprintln!("warning: could not load known answers: {}", e);
+            }
+            Some(Answers::default())
+        }
+    }
+}
+
+fn run(options: &Options, config: &Config, answers: &Answers) -> bool {
+    let mut ok = true;
+    let mut planned = Vec::new();
+    for &day in &options.days {
+        let Some(solution) = solutions::get(day) else {
+            eprintln!("error: day {} is not implemented yet", day);
+            ok = false;
+            continue;
+        };
+        for (dataset, parts) in runs(day, options, answers) {
+            planned.push(Run {
+                day: solution,
+                dataset,
+                parts,
+            });
+        }
+    }
+
+    runner::capture_panics();
+    let start = Instant::now();
+    let read = |run: &Run| {
+        let dataset = run.dataset.as_ref();
+        read_input(run.day.number, dataset, options.input.as_ref(), config)
+    };
+    let records = match options.format {
+        Format::Text => print_text(options, &planned, read, answers, &config.limits),
+        format => {
+            let records = report(format, &planned, read, answers, &config.limits, 1);
+            print!("{}", report::end(format, &records, start.elapsed()));
+            records
+        }
+    };
+    ok && records.iter().all(|r| match r.status {
+        Status::Pass | Status::Unknown => true,
+        Status::Fail => !options.check,
+        Status::Error(_) | Status::Panicked(_) | Status::Timeout(_) | Status::OutOfMemory(_) => {
+            false
+        }
+    })
+}
+
+/// Executes `runs`, printing each answer under a heading for its day and
+/// dataset, and with `--check`, how it compares to the known answer.
+fn print_text(
+    options: &Options,
+    runs: &[Run],
+    read: impl Fn(&Run) -> Result<String> + Sync,
+    answers: &Answers,
+    limits: &Limits,
+) -> Vec<Record> {
+    let mut last = None;
+    runner::execute_all(runs, read, answers, limits, 1, |record| {
+        let run = Some((record.day, record.dataset.clone()));
+        let first = last != run;
+        last = run;
+        if let Status::Error(message) = &record.status {
+            // Every part of a run that fails to parse has the same error.
+            if first {
+                eprintln!("error: {}", message);
+            }
+            return;
+        }
+        if first {
+            match &record.dataset {
+                Some(Dataset::Example(name)) => println!("Day {} ({})", record.day, name),
+                _ => println!("Day {}", record.day),
+            }
+        }
+
+        let name = match record.part {
+            Part::One => "one",
+            Part::Two => "two",
+        };
+        let answer = match (&record.answer, &record.status) {
+            (Some(answer), _) => answer,
+            (None, status) => {
+                let message = record.message().unwrap_or_default();
+                eprintln!(
+                    "error: day {} part {} {}: {}",
+                    record.day, name, status, message
+                );
+                return;
+            }
+        };
+        if !options.check {
+            println!("Part {}: {}", name, answer);
+            return;
+        }
+        let check = match (&record.status, &record.expected) {
+            (Status::Pass, _) => Check::Pass,
+            (Status::Fail, Some(expected)) => Check::Fail {
+                expected: expected.clone(),
+            },
+            _ => Check::Unknown,
+        };
+        println!("Part {}: {} [{}]", name, answer, check);
+    })
+}
+
+fn bench(options: &Options, config: &Config, answers: &Answers) -> bool {
+    let mut baseline = match Baseline::load(config.baseline_path()) {
+        Ok(baseline) => baseline,
+        Err(Error::Io { .. }) => Baseline::default(),
+        Err(e) => {
+            eprintln!("warning: ignoring bench baseline: {}", e);
+            Baseline::default()
+        }
+    };
+
+    println!(
+        "{:<4} {:<12} {:<6} {:>12} {:>12} {:>12} {:>9}",
+        "Day", "Dataset", "Phase", "Min", "Median", "Mean", "Baseline"
+    );
+    let mut ok = true;
+    let mut medians = Vec::new();
+    for &day in &options.days {
+        let Some(solution) = solutions::get(day) else {
+            eprintln!("error: day {} is not implemented yet", day);
+            ok = false;
+            continue;
+        };
+        for (dataset, parts) in runs(day, options, answers) {
+            let label = dataset
+                .as_ref()
+                .map_or("input".to_string(), |d| d.to_string());
+            let input = read_input(day, dataset.as_ref(), options.input.as_ref(), config);
+            let phases = match input
+                .and_then(|input| bench::run(solution, &input, &parts, options.iterations))
+            {
+                Ok(phases) => phases,
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    ok = false;
+                    continue;
+                }
+            };
+            for (phase, stats) in phases {
+                println!(
+                    "{:<4} {:<12} {:<6} {:>12} {:>12} {:>12} {:>9}",
+                    day,
+                    label,
+                    phase,
+                    format!("{:.2?}", stats.min),
+                    format!("{:.2?}", stats.median),
+                    format!("{:.2?}", stats.mean),
+                    bench::change(stats.median, baseline.get(day, &label, phase)),
+                );
+                medians.push((day, label.clone(), phase, stats.median));
+            }
+        }
+    }
+
+    if options.save_baseline {
+        for (day, label, phase, median) in medians {
+            baseline.insert(day, &label, phase, median);
+        }
+        if let Err(e) = baseline.save(config.baseline_path()) {
+            eprintln!("error: could not save bench baseline: {}", e);
+            ok = false;
+        }
+    }
+    ok
+}
+
+/// Works out which dataset each requested part of `day` runs against, grouping
+/// the parts that share one. `None` stands for the input given with `--input`.
+fn runs(day: u32, options: &Options, answers: &Answers) -> Vec<(Option<Dataset>, Vec<Part>)> {
+    if options.input.is_some() {
+        return Vec::from([(None, options.parts.clone())]);
+    }
+
+    let mut runs: Vec<(Option<Dataset>, Vec<Part>)> = Vec::new();
+    for &part in &options.parts {
+        let datasets = match (&options.example, options.test_mode) {
+            (Some(example), _) => Vec::from([example.clone()]),
+            (None, true) => answers.examples(day, part),
+            (None, false) => Vec::from([Dataset::Real]),
+        };
+        for dataset in datasets {
+            match runs.iter_mut().find(|(d, _)| d.as_ref() == Some(&dataset)) {
+                Some((_, parts)) => parts.push(part),
+                None => runs.push((Some(dataset), Vec::from([part]))),
+            }
+        }
+    }
+    runs
+}
+
+fn read_input(
+    day: u32,
+    dataset: Option<&Dataset>,
+    input: Option<&Input>,
+    config: &Config,
+) -> Result<String> {
+    match (input, dataset) {
+        (Some(Input::Stdin), _) => utils::read_stdin_as_string(),
+        (Some(Input::File(path)), _) => utils::read_file_as_string(path),
+        (None, Some(dataset)) => manifest::read_checked(config.input_path(day, dataset)),
+        (None, None) => unreachable!("runs without --input always have a dataset"),
     }
 }
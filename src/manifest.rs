@@ -0,0 +1,341 @@
+//! SHA-1 digests of the data files, to catch inputs that were truncated,
+//! edited or swapped by accident.
+//!
+//! Each data directory has a `manifest.sha1` in the format written by
+//! `sha1sum`, so it can also be checked with `sha1sum -c manifest.sha1`.
+
+use crate::error::{Error, Result};
+use crate::utils;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "manifest.sha1";
+
+/// The SHA-1 digest of `contents` in lowercase hex.
+pub fn digest(contents: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(contents))
+}
+
+/// The digests recorded for the data files in one directory, by file name.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub digests: BTreeMap<String, String>,
+}
+impl Manifest {
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(MANIFEST_FILE)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Manifest> {
+        let contents = utils::read_file_as_string(path)?;
+        let mut digests = BTreeMap::new();
+        for line in contents.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            // `sha1sum` separates the digest from the name with two spaces, or
+            // with a space and `*` for files it read in binary mode.
+            let Some((digest, name)) = line.split_once("  ").or_else(|| line.split_once(" *"))
+            else {
+                return Err(Error::at(&contents, line, "expected `<sha1>  <file>`"));
+            };
+            if digest.len() != 40 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(Error::at(&contents, digest, "expected a SHA-1 digest"));
+            }
+            digests.insert(name.to_string(), digest.to_ascii_lowercase());
+        }
+        Ok(Manifest { digests })
+    }
+
+    /// Loads the manifest of `dir`, or returns `None` if it has none.
+    pub fn load_from(dir: &Path) -> Result<Option<Manifest>> {
+        let path = Manifest::path(dir);
+        match path.is_file() {
+            true => Manifest::load(path).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = self
+            .digests
+            .iter()
+            .map(|(name, digest)| format!("{}  {}\n", digest, name))
+            .collect::<String>();
+        fs::write(path, contents).map_err(|e| Error::io(path, e))
+    }
+}
+
+/// How a data file compares to the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum State {
+    Ok,
+    Changed {
+        expected: String,
+        actual: String,
+    },
+    /// Listed in the manifest but not on disk.
+    Missing,
+    /// On disk but not listed in the manifest.
+    Unlisted,
+}
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            State::Ok => "ok",
+            State::Changed { .. } => "CHANGED",
+            State::Missing => "MISSING",
+            State::Unlisted => "UNLISTED",
+        })
+    }
+}
+
+/// Compares the data files in `dir` against its manifest, by file name. Only
+/// the files of `days` are compared, or all of them if `days` is empty.
+pub fn verify(dir: &Path, days: &[u32]) -> Result<Vec<(String, State)>> {
+    let manifest = Manifest::load(Manifest::path(dir))?;
+    compare(dir, &manifest, days)
+}
+
+/// Records the digests of the data files of `days` in `dir`, or of all of
+/// them if `days` is empty, and forgets files that are gone. Returns how each
+/// file compared to the manifest before, which is created if there was none.
+pub fn record(dir: &Path, days: &[u32]) -> Result<Vec<(String, State)>> {
+    let mut manifest = Manifest::load_from(dir)?.unwrap_or_default();
+    let states = compare(dir, &manifest, days)?;
+    for (name, state) in &states {
+        match state {
+            State::Ok => {}
+            State::Changed { actual, .. } => {
+                manifest.digests.insert(name.clone(), actual.clone());
+            }
+            State::Unlisted => {
+                let digest = digest_of(&dir.join(name))?;
+                manifest.digests.insert(name.clone(), digest);
+            }
+            State::Missing => {
+                manifest.digests.remove(name);
+            }
+        }
+    }
+    manifest.save(Manifest::path(dir))?;
+    Ok(states)
+}
+
+/// Fails if `contents`, read from `path`, don't match the digest recorded for
+/// it. Files without a manifest, or not listed in theirs, always pass.
+pub fn check(path: &Path, contents: &str) -> Result<()> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|n| n.to_str())) else {
+        return Ok(());
+    };
+    let Some(manifest) = Manifest::load_from(dir)? else {
+        return Ok(());
+    };
+    let Some(expected) = manifest.digests.get(name) else {
+        return Ok(());
+    };
+    let actual = digest(contents.as_bytes());
+    match &actual == expected {
+        true => Ok(()),
+        false => Err(Error::Integrity(format!(
+            "{} does not match {} (expected sha1 {}, found {}); run `manifest` if the change is intended",
+            path.display(),
+            MANIFEST_FILE,
+            expected,
+            actual
+        ))),
+    }
+}
+
+/// Reads the data file at `path` and checks it against its manifest.
+pub fn read_checked(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let contents = utils::read_file_as_string(path)?;
+    check(path, &contents)?;
+    Ok(contents)
+}
+
+fn compare(dir: &Path, manifest: &Manifest, days: &[u32]) -> Result<Vec<(String, State)>> {
+    let wanted =
+        |name: &str| days.is_empty() || days.iter().any(|&day| utils::is_day_file(name, day));
+    let mut states = BTreeMap::new();
+    for name in data_files(dir)? {
+        if !wanted(&name) {
+            continue;
+        }
+        let state = match manifest.digests.get(&name) {
+            None => State::Unlisted,
+            Some(expected) => {
+                let actual = digest_of(&dir.join(&name))?;
+                match &actual == expected {
+                    true => State::Ok,
+                    false => State::Changed {
+                        expected: expected.clone(),
+                        actual,
+                    },
+                }
+            }
+        };
+        states.insert(name, state);
+    }
+    for name in manifest.digests.keys() {
+        if wanted(name) && !states.contains_key(name) {
+            states.insert(name.clone(), State::Missing);
+        }
+    }
+    Ok(states.into_iter().collect())
+}
+
+/// The names of the `.data` files directly in `dir`.
+fn data_files(dir: &Path) -> Result<Vec<String>> {
+    let entries = fs::read_dir(dir).map_err(|e| Error::io(dir, e))?;
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::io(dir, e))?;
+        let is_file = entry.file_type().is_ok_and(|t| t.is_file());
+        if let Some(name) = entry.file_name().to_str() {
+            if is_file && name.ends_with(".data") {
+                names.push(name.to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+fn digest_of(path: &Path) -> Result<String> {
+    let contents = fs::read(path).map_err(|e| Error::io(path, e))?;
+    Ok(digest(&contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TempDir;
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn states(list: &[(&str, State)]) -> Vec<(String, State)> {
+        list.iter()
+            .map(|(name, state)| (name.to_string(), state.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn digests_match_sha1sum() {
+        assert_eq!(digest(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+
+        let manifest = "A9993E364706816ABA3E25717850C26C9CD0D89D *day1.data\n\n\
+                        da39a3ee5e6b4b0d3255bfef95601890afd80709  day1_test.data\n";
+        let temp = TempDir::new("manifest-load");
+        let path = write(temp.path(), MANIFEST_FILE, manifest);
+        let loaded = Manifest::load(&path).unwrap();
+        assert_eq!(
+            loaded.digests.into_iter().collect::<Vec<_>>(),
+            [
+                ("day1.data".to_string(), digest(b"abc")),
+                ("day1_test.data".to_string(), digest(b"")),
+            ]
+        );
+
+        write(temp.path(), MANIFEST_FILE, "a9993e36  day1.data\n");
+        let error = Manifest::load(&path).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 1: expected a SHA-1 digest"
+        );
+        write(temp.path(), MANIFEST_FILE, "\nday1.data\n");
+        let error = Manifest::load(&path).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1: expected `<sha1>  <file>`"
+        );
+    }
+
+    #[test]
+    fn changed_files_fail_their_check() {
+        let temp = TempDir::new("manifest-changed");
+        let dir = temp.path();
+        let real = write(dir, "day1.data", "1abc2\n");
+        let example = write(dir, "day1_test.data", "treb7uchet\n");
+        write(dir, "notes.txt", "not data");
+
+        assert_eq!(
+            record(dir, &[]).unwrap(),
+            states(&[
+                ("day1.data", State::Unlisted),
+                ("day1_test.data", State::Unlisted),
+            ])
+        );
+        assert_eq!(
+            fs::read_to_string(Manifest::path(dir)).unwrap(),
+            format!(
+                "{}  day1.data\n{}  day1_test.data\n",
+                digest(b"1abc2\n"),
+                digest(b"treb7uchet\n")
+            )
+        );
+        assert_eq!(read_checked(&real).unwrap(), "1abc2\n");
+
+        fs::write(&real, "1abc3\n").unwrap();
+        let changed = State::Changed {
+            expected: digest(b"1abc2\n"),
+            actual: digest(b"1abc3\n"),
+        };
+        assert_eq!(
+            verify(dir, &[]).unwrap(),
+            states(&[
+                ("day1.data", changed.clone()),
+                ("day1_test.data", State::Ok)
+            ])
+        );
+        assert!(
+            matches!(read_checked(&real), Err(Error::Integrity(m)) if m.contains("does not match manifest.sha1")),
+        );
+        assert_eq!(read_checked(&example).unwrap(), "treb7uchet\n");
+
+        // Recording again accepts the change.
+        assert_eq!(
+            record(dir, &[1]).unwrap(),
+            states(&[("day1.data", changed), ("day1_test.data", State::Ok)])
+        );
+        assert_eq!(read_checked(&real).unwrap(), "1abc3\n");
+    }
+
+    #[test]
+    fn files_missing_from_either_side() {
+        let temp = TempDir::new("manifest-missing");
+        let dir = temp.path();
+        let real = write(dir, "day2.data", "Game 1: 3 blue\n");
+
+        // Without a manifest, nothing is checked but there is nothing to verify.
+        assert_eq!(read_checked(&real).unwrap(), "Game 1: 3 blue\n");
+        assert!(matches!(verify(dir, &[]), Err(Error::Io { .. })));
+
+        record(dir, &[2]).unwrap();
+        let unlisted = write(dir, "day3.data", "467..114..\n");
+        assert_eq!(read_checked(&unlisted).unwrap(), "467..114..\n");
+        fs::remove_file(&real).unwrap();
+        assert_eq!(
+            verify(dir, &[]).unwrap(),
+            states(&[
+                ("day2.data", State::Missing),
+                ("day3.data", State::Unlisted)
+            ])
+        );
+        assert_eq!(
+            verify(dir, &[3]).unwrap(),
+            states(&[("day3.data", State::Unlisted)])
+        );
+
+        record(dir, &[2]).unwrap();
+        assert_eq!(Manifest::load_from(dir).unwrap(), Some(Manifest::default()));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::runner::{Record, Status};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How results are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// For people to read.
+    Text,
+    /// An object with a `results` array, one object per record, and a
+    /// `summary` of how many records had each status.
+    Json,
+    /// One record per row with a header row, times in nanoseconds, and the
+    /// message of any error, panic or exceeded limit in the last column.
+    Csv,
+    /// The Test Anything Protocol, one test per record, with answers and
+    /// timings in each test's YAML block.
+    Tap,
+}
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "tap" => Ok(Format::Tap),
+            _ => Err(format!(
+                "invalid format `{}`: expected `text`, `json`, `csv` or `tap`",
+                s
+            )),
+        }
+    }
+}
+
+/// How many records ended with each status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub unknown: usize,
+    pub errors: usize,
+    pub panicked: usize,
+    pub timed_out: usize,
+    pub out_of_memory: usize,
+}
+impl Summary {
+    pub fn of(records: &[Record]) -> Summary {
+        let mut summary = Summary::default();
+        for record in records {
+            match record.status {
+                Status::Pass => summary.passed += 1,
+                Status::Fail => summary.failed += 1,
+                Status::Unknown => summary.unknown += 1,
+                Status::Error(_) => summary.errors += 1,
+                Status::Panicked(_) => summary.panicked += 1,
+                Status::Timeout(_) => summary.timed_out += 1,
+                Status::OutOfMemory(_) => summary.out_of_memory += 1,
+            }
+        }
+        summary
+    }
+}
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} passed, {} failed, {} unknown, {} errors, {} panicked, {} timed out, {} out of memory",
+            self.passed,
+            self.failed,
+            self.unknown,
+            self.errors,
+            self.panicked,
+            self.timed_out,
+            self.out_of_memory
+        )
+    }
+}
+
+pub const CSV_COLUMNS: [&str; 9] = [
+    "day", "dataset", "part", "answer", "expected", "status", "parse_ns", "solve_ns", "message",
+];
+
+/// What to write before the first of `total` records, if anything.
+pub fn begin(format: Format, total: usize) -> String {
+    match format {
+        Format::Text => format!(
+            "{:<4} {:<12} {:<4} {:>16} {:>16} {:<7} {:>10} {:>10}\n",
+            "Day", "Dataset", "Part", "Answer", "Expected", "Status", "Parse", "Solve"
+        ),
+        Format::Json => "{\n  \"results\": [".to_string(),
+        Format::Csv => format!("{}\n", CSV_COLUMNS.join(",")),
+        Format::Tap => format!("TAP version 13\n1..{}\n", total),
+    }
+}
+
+/// The record at `index`, counting from 0.
+pub fn row(format: Format, index: usize, record: &Record) -> String {
+    let answer = record.answer.as_ref().map(|a| a.to_string());
+    match format {
+        Format::Text => format!(
+            "{:<4} {:<12} {:<4} {:>16} {:>16} {:<7} {:>10} {:>10}\n",
+            record.day,
+            record.dataset_name(),
+            record.part.number(),
+            answer.map_or("-".to_string(), |a| a.replace('\n', "\\n")),
+            record.expected.as_deref().unwrap_or("-"),
+            record.status,
+            format!("{:.2?}", record.parse_time),
+            format!("{:.2?}", record.solve_time),
+        ),
+        Format::Json => format!(
+            "{}\n    {{\"day\": {}, \"dataset\": {}, \"part\": {}, \"answer\": {}, \"expected\": {}, \"status\": {}, \"parse_ns\": {}, \"solve_ns\": {}, \"message\": {}}}",
+            if index == 0 { "" } else { "," },
+            record.day,
+            json_string(&record.dataset_name()),
+            record.part.number(),
+            answer.as_deref().map_or("null".to_string(), json_string),
+            record.expected.as_deref().map_or("null".to_string(), json_string),
+            json_string(&status_name(&record.status)),
+            record.parse_time.as_nanos(),
+            record.solve_time.as_nanos(),
+            record.message().map_or("null".to_string(), json_string),
+        ),
+        Format::Csv => {
+            let fields = [
+                record.day.to_string(),
+                record.dataset_name(),
+                record.part.number().to_string(),
+                answer.unwrap_or_default(),
+                record.expected.clone().unwrap_or_default(),
+                status_name(&record.status),
+                record.parse_time.as_nanos().to_string(),
+                record.solve_time.as_nanos().to_string(),
+                record.message().unwrap_or_default().to_string(),
+            ];
+            let fields = fields.iter().map(|f| csv_field(f)).collect::<Vec<String>>();
+            format!("{}\n", fields.join(","))
+        }
+        Format::Tap => {
+            let ok = match record.status {
+                Status::Pass | Status::Unknown => "ok",
+                _ => "not ok",
+            };
+            let directive = match record.status {
+                Status::Unknown => " # SKIP no known answer",
+                _ => "",
+            };
+            let mut yaml = Vec::new();
+            if let Some(answer) = &answer {
+                yaml.push(format!("answer: {}", json_string(answer)));
+            }
+            if let Some(expected) = &record.expected {
+                yaml.push(format!("expected: {}", json_string(expected)));
+            }
+            yaml.push(format!("status: {}", status_name(&record.status)));
+            if let Some(message) = record.message() {
+                yaml.push(format!("message: {}", json_string(message)));
+            }
+            yaml.push(format!("parse_ms: {}", millis(record.parse_time)));
+            yaml.push(format!("duration_ms: {}", millis(record.solve_time)));
+            format!(
+                "{} {} - day {} {} part {}{}\n  ---\n{}  ...\n",
+                ok,
+                index + 1,
+                record.day,
+                record.dataset_name(),
+                record.part.number(),
+                directive,
+                yaml.iter()
+                    .map(|line| format!("  {}\n", line))
+                    .collect::<String>()
+            )
+        }
+    }
+}
+
+/// What to write after the last record, if anything.
+pub fn end(format: Format, records: &[Record], elapsed: Duration) -> String {
+    let summary = Summary::of(records);
+    match format {
+        Format::Text => format!("\n{} in {:.2?}\n", summary, elapsed),
+        Format::Json => format!(
+            "\n  ],\n  \"summary\": {{\"passed\": {}, \"failed\": {}, \"unknown\": {}, \"errors\": {}, \"panicked\": {}, \"timed_out\": {}, \"out_of_memory\": {}, \"elapsed_ns\": {}}}\n}}\n",
+            summary.passed,
+            summary.failed,
+            summary.unknown,
+            summary.errors,
+            summary.panicked,
+            summary.timed_out,
+            summary.out_of_memory,
+            elapsed.as_nanos()
+        ),
+        Format::Csv => String::new(),
+        Format::Tap => format!("# {} in {:.2?}\n", summary, elapsed),
+    }
+}
+
+/// The status as written in machine-readable formats: `pass`, `fail`,
+/// `unknown`, `error`, `panic`, `timeout` or `oom`.
+fn status_name(status: &Status) -> String {
+    status.to_string().to_lowercase()
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+/// Quotes and escapes `s` as a JSON string, which is also a valid YAML scalar.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quotes `field` if it holds a comma, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answers::Answers;
+    use crate::solutions::{Answer, Part};
+    use crate::utils::Dataset;
+
+    fn record(status: Status, answer: Option<Answer>) -> Record {
+        Record {
+            answer,
+            status,
+            ..Record::new(5, Some(Dataset::Real), Part::Two, &Answers::default())
+        }
+    }
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(
+            json_string("say \"hi\"\\\tthen\r\n\u{1}"),
+            "\"say \\\"hi\\\"\\\\\\tthen\\r\\n\\u0001\""
+        );
+
+        let drawn = Answer::Lines(Vec::from(["#\"#".to_string(), "\\.\\".to_string()]));
+        let row = row(Format::Json, 1, &record(Status::Unknown, Some(drawn)));
+        assert!(row.starts_with(",\n    {\"day\": 5, \"dataset\": \"real\", \"part\": 2, "));
+        assert!(row.contains("\"answer\": \"#\\\"#\\n\\\\.\\\\\", \"expected\": null, "));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("1234"), "1234");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("#.\n.#"), "\"#.\n.#\"");
+
+        let panicked = record(Status::Panicked("index 3, len 2".to_string()), None);
+        assert_eq!(
+            row(Format::Csv, 0, &panicked),
+            "5,real,2,,,panic,0,0,\"index 3, len 2\"\n"
+        );
+    }
+
+    #[test]
+    fn tap_plans_and_fails_tests() {
+        assert_eq!(begin(Format::Tap, 7), "TAP version 13\n1..7\n");
+
+        let passed = row(
+            Format::Tap,
+            0,
+            &record(Status::Pass, Some(Answer::Number(42))),
+        );
+        assert!(passed.starts_with("ok 1 - day 5 real part 2\n  ---\n  answer: \"42\"\n"));
+        let unknown = row(
+            Format::Tap,
+            1,
+            &record(Status::Unknown, Some(Answer::Number(42))),
+        );
+        assert!(unknown.starts_with("ok 2 - day 5 real part 2 # SKIP no known answer\n"));
+
+        for (status, name) in [
+            (Status::Fail, "fail"),
+            (Status::Panicked("boom".to_string()), "panic"),
+            (Status::Timeout("took more than 1s".to_string()), "timeout"),
+        ] {
+            let failed = row(Format::Tap, 2, &record(status.clone(), None));
+            assert!(
+                failed.starts_with("not ok 3 - day 5 real part 2\n  ---\n"),
+                "{}",
+                failed
+            );
+            assert!(
+                failed.contains(&format!("  status: {}\n", name)),
+                "{}",
+                failed
+            );
+            assert_eq!(
+                failed.contains("  message: "),
+                status != Status::Fail,
+                "{}",
+                failed
+            );
+            assert!(failed.ends_with("  ...\n"));
+        }
+    }
+}
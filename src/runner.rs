@@ -0,0 +1,448 @@
+use crate::answers::{Answers, Check};
+use crate::error::Result;
+use crate::isolate;
+use crate::solutions::{Answer, Day, Part, Solution};
+use crate::utils::Dataset;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One day run against one dataset, for the parts that dataset is used for.
+/// A dataset of `None` stands for input given with `--input`.
+#[derive(Clone)]
+pub struct Run {
+    pub day: &'static Day,
+    pub dataset: Option<Dataset>,
+    pub parts: Vec<Part>,
+}
+
+/// Every run needed to check `days`: each part against its examples, then
+/// both parts against the real input.
+pub fn plan(days: &[&'static Day], answers: &Answers) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for &day in days {
+        for part in [Part::One, Part::Two] {
+            for dataset in answers.examples(day.number, part) {
+                match runs
+                    .iter_mut()
+                    .find(|r| r.day.number == day.number && r.dataset.as_ref() == Some(&dataset))
+                {
+                    Some(run) => run.parts.push(part),
+                    None => runs.push(Run {
+                        day,
+                        dataset: Some(dataset),
+                        parts: Vec::from([part]),
+                    }),
+                }
+            }
+        }
+        runs.push(Run {
+            day,
+            dataset: Some(Dataset::Real),
+            parts: Vec::from([Part::One, Part::Two]),
+        });
+    }
+    runs
+}
+
+/// How long, and with how much memory, parsing an input and solving each part
+/// may run. `None` is no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub timeout: Option<Duration>,
+    /// In bytes of heap. Only enforced on unix.
+    pub memory: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail,
+    /// No answer is known for this day, part and dataset.
+    Unknown,
+    /// The input could not be read or parsed.
+    Error(String),
+    Panicked(String),
+    /// Parsing or solving ran past the time limit and was killed.
+    Timeout(String),
+    /// Parsing or solving tried to go over the memory limit and was stopped.
+    OutOfMemory(String),
+}
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Pass => f.pad("pass"),
+            Status::Fail => f.pad("FAIL"),
+            Status::Unknown => f.pad("unknown"),
+            Status::Error(_) => f.pad("ERROR"),
+            Status::Panicked(_) => f.pad("PANIC"),
+            Status::Timeout(_) => f.pad("TIMEOUT"),
+            Status::OutOfMemory(_) => f.pad("OOM"),
+        }
+    }
+}
+
+/// The result of one part of one run.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub day: u32,
+    pub dataset: Option<Dataset>,
+    pub part: Part,
+    pub answer: Option<Answer>,
+    pub expected: Option<String>,
+    pub status: Status,
+    /// How long parsing the input took. Without limits, every part of a run
+    /// shares one parse.
+    pub parse_time: Duration,
+    pub solve_time: Duration,
+}
+impl Record {
+    /// A record with nothing run yet, expecting the known answer, if any.
+    pub fn new(day: u32, dataset: Option<Dataset>, part: Part, answers: &Answers) -> Record {
+        let expected = dataset
+            .as_ref()
+            .and_then(|dataset| answers.get(day, part, dataset))
+            .map(|s| s.to_string());
+        Record {
+            day,
+            dataset,
+            part,
+            answer: None,
+            expected,
+            status: Status::Unknown,
+            parse_time: Duration::ZERO,
+            solve_time: Duration::ZERO,
+        }
+    }
+
+    /// The record with `solved` and its check against `answers`, or the
+    /// status it failed with.
+    pub fn checked(self, solved: std::result::Result<Answer, Status>, answers: &Answers) -> Record {
+        let answer = match solved {
+            Ok(answer) => answer,
+            Err(status) => return Record { status, ..self },
+        };
+        // Answers are only known for the checked-in data files.
+        let check = match &self.dataset {
+            Some(dataset) => answers.check(self.day, self.part, dataset, &answer),
+            None => Check::Unknown,
+        };
+        let status = match check {
+            Check::Pass => Status::Pass,
+            Check::Fail { .. } => Status::Fail,
+            Check::Unknown => Status::Unknown,
+        };
+        Record {
+            answer: Some(answer),
+            status,
+            ..self
+        }
+    }
+
+    /// The dataset's name, or `input` for input given with `--input`.
+    pub fn dataset_name(&self) -> String {
+        self.dataset
+            .as_ref()
+            .map_or("input".to_string(), |d| d.to_string())
+    }
+
+    /// The error, panic or limit message, if there is one.
+    pub fn message(&self) -> Option<&str> {
+        match &self.status {
+            Status::Error(message)
+            | Status::Panicked(message)
+            | Status::Timeout(message)
+            | Status::OutOfMemory(message) => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// A run's input, made ready for the run's parts.
+enum Prepared {
+    /// Parsed here, and shared by the parts.
+    Parsed {
+        solution: std::result::Result<Arc<dyn Solution>, Status>,
+        parse_time: Duration,
+    },
+    /// Only read. With limits, each part parses it again in a child process
+    /// of its own.
+    Read(std::result::Result<String, Status>),
+}
+
+fn prepare(run: &Run, read: &impl Fn(&Run) -> Result<String>, limits: &Limits) -> Prepared {
+    let input = read(run).map_err(|e| Status::Error(e.to_string()));
+    if *limits != Limits::default() {
+        return Prepared::Read(input);
+    }
+    let input = match input {
+        Ok(input) => input,
+        Err(status) => {
+            return Prepared::Parsed {
+                solution: Err(status),
+                parse_time: Duration::ZERO,
+            }
+        }
+    };
+    let (parsed, parse_time) = timed(|| run.day.parse(&input));
+    let solution = match parsed {
+        Ok(Ok(solution)) => Ok(Arc::from(solution)),
+        Ok(Err(e)) => Err(Status::Error(e.to_string())),
+        Err(message) => Err(Status::Panicked(message)),
+    };
+    Prepared::Parsed {
+        solution,
+        parse_time,
+    }
+}
+
+fn solve(run: &Run, part: Part, prepared: &Prepared, answers: &Answers, limits: &Limits) -> Record {
+    let day = run.day.number;
+    let (solved, parse_time, solve_time) = match prepared {
+        Prepared::Parsed {
+            solution: Ok(solution),
+            parse_time,
+        } => {
+            let (solved, solve_time) = timed(|| solution.solve(part));
+            (solved.map_err(Status::Panicked), *parse_time, solve_time)
+        }
+        Prepared::Parsed {
+            solution: Err(status),
+            parse_time,
+        } => (Err(status.clone()), *parse_time, Duration::ZERO),
+        Prepared::Read(Ok(input)) => isolate::solve(day, part, input, limits),
+        Prepared::Read(Err(status)) => (Err(status.clone()), Duration::ZERO, Duration::ZERO),
+    };
+    let record = Record {
+        parse_time,
+        solve_time,
+        ..Record::new(day, run.dataset.clone(), part, answers)
+    };
+    record.checked(solved, answers)
+}
+
+/// Reads the input for each of `runs` with `read`, parses it and solves each of its parts,
+/// checking the answers against `answers`. Errors, panics and going over
+/// `limits` are recorded against the parts they affect rather than returned,
+/// so one broken day cannot stop a whole suite.
+///
+/// Runs go on `jobs` threads, and the parts of a run are solved at the same
+/// time once its input is parsed. With limits, each part is parsed and solved
+/// in a child process instead, which is killed if it runs out of time. `report` is called with each record as soon
+/// as it and every record before it are done, so records come out in the same
+/// order whatever the number of jobs.
+pub fn execute_all(
+    runs: &[Run],
+    read: impl Fn(&Run) -> Result<String> + Sync,
+    answers: &Answers,
+    limits: &Limits,
+    jobs: usize,
+    mut report: impl FnMut(&Record),
+) -> Vec<Record> {
+    let tasks = runs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, run)| run.parts.iter().map(move |&part| (i, part)))
+        .collect::<Vec<(usize, Part)>>();
+    let prepared = runs
+        .iter()
+        .map(|_| OnceLock::new())
+        .collect::<Vec<OnceLock<Prepared>>>();
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    let mut records = Vec::with_capacity(tasks.len());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, tasks.len().max(1)) {
+            let sender = sender.clone();
+            let (tasks, prepared, next, read) = (&tasks, &prepared, &next, &read);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&(run, part)) = tasks.get(index) else {
+                    break;
+                };
+                // The first part of a run to be picked up prepares its input;
+                // any other part waits for that instead of doing it again.
+                let prepared = prepared[run].get_or_init(|| prepare(&runs[run], read, limits));
+                let record = solve(&runs[run], part, prepared, answers, limits);
+                if sender.send((index, record)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut done = BTreeMap::new();
+        for (index, record) in receiver {
+            done.insert(index, record);
+            while let Some(record) = done.remove(&records.len()) {
+                report(&record);
+                records.push(record);
+            }
+        }
+    });
+    records
+}
+
+/// Calls `f` and times it, turning a panic into its message.
+fn timed<T>(f: impl FnOnce() -> T) -> (std::result::Result<T, String>, Duration) {
+    let start = Instant::now();
+    let result = catch(f);
+    (result, start.elapsed())
+}
+
+thread_local! {
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Replaces the default panic message on stderr with a note of where the panic
+/// happened, which `execute_all` adds to the panic's record instead.
+pub fn capture_panics() {
+    panic::set_hook(Box::new(|info| {
+        let location = info.location().map(|l| l.to_string());
+        PANIC_LOCATION.with(|cell| *cell.borrow_mut() = location);
+    }));
+}
+
+/// Calls `f`, turning a panic into its message.
+pub(crate) fn catch<T>(f: impl FnOnce() -> T) -> std::result::Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = panic_message(payload.as_ref());
+        match PANIC_LOCATION.with(|cell| cell.borrow_mut().take()) {
+            Some(location) => format!("{} at {}", message, location),
+            None => message,
+        }
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::solutions::{self, DAYS};
+
+    /// A day whose part one always panics.
+    struct Panics;
+    impl Solution for Panics {
+        fn parse(_: &str) -> Result<Self> {
+            Ok(Panics)
+        }
+
+        fn part_one(&self) -> Answer {
+            panic!("no part one")
+        }
+
+        fn part_two(&self) -> Answer {
+            Answer::Number(2)
+        }
+    }
+
+    static PANICS: Day = Day::new::<Panics>(1);
+
+    /// Every example run of every day, with the checked-in answers.
+    fn examples() -> (Vec<Run>, Answers) {
+        let answers = Answers::load(Config::default().answers_path()).unwrap();
+        let days = DAYS.iter().collect::<Vec<_>>();
+        let runs = plan(&days, &answers)
+            .into_iter()
+            .filter(|run| run.dataset != Some(Dataset::Real))
+            .collect();
+        (runs, answers)
+    }
+
+    fn read(run: &Run) -> Result<String> {
+        Ok(solutions::read_data(
+            run.day.number,
+            run.dataset.as_ref().unwrap(),
+        ))
+    }
+
+    #[test]
+    fn records_come_out_in_order_whatever_the_jobs() {
+        let (runs, answers) = examples();
+        let expected = runs
+            .iter()
+            .flat_map(|run| {
+                run.parts
+                    .iter()
+                    .map(|&part| (run.day.number, run.dataset.clone(), part))
+            })
+            .collect::<Vec<_>>();
+        assert!(runs.len() > 4);
+
+        // The first run is held up so that the others finish before it.
+        let slow_read = |run: &Run| {
+            if run.day.number == runs[0].day.number {
+                thread::sleep(Duration::from_millis(200));
+            }
+            read(run)
+        };
+        let mut reported = Vec::new();
+        let records = execute_all(
+            &runs,
+            slow_read,
+            &answers,
+            &Limits::default(),
+            4,
+            |record| reported.push((record.day, record.dataset.clone(), record.part)),
+        );
+        let order = records
+            .iter()
+            .map(|record| (record.day, record.dataset.clone(), record.part))
+            .collect::<Vec<_>>();
+        assert_eq!(order, expected);
+        assert_eq!(reported, expected);
+        for record in &records {
+            assert_eq!(record.status, Status::Pass, "{:?}", record);
+        }
+    }
+
+    #[test]
+    fn panics_are_recorded_and_the_rest_still_run() {
+        let (examples, answers) = examples();
+        let mut runs = Vec::from([Run {
+            day: &PANICS,
+            dataset: Some(Dataset::default_example()),
+            parts: Vec::from([Part::One, Part::Two]),
+        }]);
+        runs.extend(examples);
+        let read = |run: &Run| match std::ptr::eq(run.day, &PANICS) {
+            true => Ok(String::new()),
+            false => read(run),
+        };
+        let records = execute_all(&runs, read, &answers, &Limits::default(), 2, |_| {});
+
+        assert_eq!(
+            records.len(),
+            2 + runs[1..].iter().map(|run| run.parts.len()).sum::<usize>()
+        );
+        assert!(
+            matches!(&records[0].status, Status::Panicked(message) if message.starts_with("no part one")),
+            "{:?}",
+            records[0]
+        );
+        assert_eq!(records[0].status.to_string(), "PANIC");
+        assert_eq!(records[0].answer, None);
+        assert_eq!(records[1].answer, Some(Answer::Number(2)));
+        for record in &records[2..] {
+            assert_eq!(record.status, Status::Pass, "{:?}", record);
+        }
+    }
+}
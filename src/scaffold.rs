@@ -0,0 +1,291 @@
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::solutions::{module_name, type_name};
+use crate::utils::{self, Dataset};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where `day`'s solution lives in the repository at `root`.
+pub fn source_path(root: &Path, day: u32) -> PathBuf {
+    root.join("src")
+        .join("solutions")
+        .join(format!("{}.rs", module_name(day)))
+}
+
+const TEMPLATE: &str = "\
+use crate::error::Result;
+use crate::solutions::{Answer, Solution};
+use crate::utils;
+
+pub struct {type} {
+    lines: Vec<String>,
+}
+impl Solution for {type} {
+    fn parse(input: &str) -> Result<Self> {
+        let lines = utils::parse_lines(input, |line| Ok(line.to_string()))?;
+        Ok({type} { lines })
+    }
+
+    fn part_one(&self) -> Answer {
+        part_one(&self.lines).into()
+    }
+
+    fn part_two(&self) -> Answer {
+        part_two(&self.lines).into()
+    }
+}
+
+fn part_one(lines: &[String]) -> usize {
+    todo!(\"day {day} part one, {} lines\", lines.len())
+}
+
+fn part_two(lines: &[String]) -> usize {
+    todo!(\"day {day} part two, {} lines\", lines.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions;
+    use crate::utils::Dataset;
+
+    #[test]
+    fn example() {
+        let input = solutions::read_data({day}, &Dataset::default_example());
+        let puzzle = {type}::parse(&input).unwrap();
+        // The answer given for the example in the puzzle.
+        assert_eq!(part_one(&puzzle.lines), 0);
+    }
+}
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Created,
+    /// The file was edited to register the new day or list its answers.
+    Updated,
+    /// The file was already there, e.g. an input fetched earlier, and was left alone.
+    Kept,
+}
+
+/// Sets up a new day in the repository at `root`: a solution module from the
+/// template, its entries in `src/solutions.rs`, an empty default example and
+/// real input, and commented-out placeholder answers to fill in once known.
+///
+/// Refuses to touch anything if the day already has a module or is already
+/// registered. Data files that already exist are kept as they are.
+pub fn scaffold(root: &Path, config: &Config, day: u32) -> Result<Vec<(Change, PathBuf)>> {
+    let module = module_name(day);
+    let source = source_path(root, day);
+    let registry = root.join("src").join("solutions.rs");
+    let registered = utils::read_file_as_string(&registry)?;
+    if source.exists() {
+        return Err(Error::Refused(format!(
+            "{} already exists",
+            source.display()
+        )));
+    }
+    if registered.contains(&format!("mod {};", module)) {
+        return Err(Error::Refused(format!(
+            "day {} is already registered in {}",
+            day,
+            registry.display()
+        )));
+    }
+    let registered = register(&registered, day).ok_or_else(|| {
+        Error::Refused(format!(
+            "could not find the module list and `DAYS` in {}",
+            registry.display()
+        ))
+    })?;
+
+    let mut changes = Vec::new();
+    let code = TEMPLATE
+        .replace("{type}", &type_name(day))
+        .replace("{day}", &day.to_string());
+    fs::write(&source, code).map_err(|e| Error::io(&source, e))?;
+    changes.push((Change::Created, source));
+    fs::write(&registry, registered).map_err(|e| Error::io(&registry, e))?;
+    changes.push((Change::Updated, registry));
+
+    for dataset in [Dataset::default_example(), Dataset::Real] {
+        let path = config.input_path(day, &dataset);
+        if path.exists() {
+            changes.push((Change::Kept, path));
+            continue;
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+        }
+        fs::write(&path, "").map_err(|e| Error::io(&path, e))?;
+        changes.push((Change::Created, path));
+    }
+
+    let path = config.answers_path();
+    let mut answers = utils::read_file_as_string(&path)?;
+    let listed = answers.lines().any(|line| {
+        line.trim_start_matches("# ")
+            .split_once(' ')
+            .is_some_and(|(d, _)| d == day.to_string())
+    });
+    if listed {
+        changes.push((Change::Kept, path));
+    } else {
+        if !answers.is_empty() && !answers.ends_with('\n') {
+            answers.push('\n');
+        }
+        for (part, dataset) in [(1, "real"), (2, "real"), (1, "test"), (2, "test")] {
+            answers.push_str(&format!("# {} {} {} <answer>\n", day, part, dataset));
+        }
+        fs::write(&path, answers).map_err(|e| Error::io(&path, e))?;
+        changes.push((Change::Updated, path));
+    }
+    Ok(changes)
+}
+
+/// Adds `day` to the source of `src/solutions.rs`: a `mod` declaration after
+/// the last one and an entry at the end of `DAYS`.
+fn register(source: &str, day: u32) -> Option<String> {
+    let module = module_name(day);
+    let mut lines = source.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+
+    let last_mod = lines
+        .iter()
+        .rposition(|line| line.starts_with("pub mod day_"))?;
+    lines.insert(last_mod + 1, format!("pub mod {};", module));
+
+    let days = lines
+        .iter()
+        .position(|line| line.starts_with("pub const DAYS"))?;
+    let end = days + lines[days..].iter().position(|line| line == "];")?;
+    lines.insert(
+        end,
+        format!("    Day::new::<{}::{}>({}),", module, type_name(day), day),
+    );
+
+    let mut source = lines.join("\n");
+    source.push('\n');
+    Some(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TempDir;
+
+    /// The registry as checked in, for `register` and `scaffold` to edit.
+    const REGISTRY: &str = include_str!("solutions.rs");
+
+    /// A repository with the checked-in registry and an answers file, but no
+    /// solutions or data.
+    fn repository(temp: &TempDir) -> Config {
+        let root = temp.path();
+        fs::create_dir_all(root.join("src").join("solutions")).unwrap();
+        fs::write(root.join("src").join("solutions.rs"), REGISTRY).unwrap();
+        let config = Config {
+            data_dir: root.join("data"),
+            ..Config::default()
+        };
+        fs::create_dir_all(&config.data_dir).unwrap();
+        fs::write(config.answers_path(), "9 1 real 42").unwrap();
+        config
+    }
+
+    fn registry(temp: &TempDir) -> String {
+        fs::read_to_string(temp.path().join("src").join("solutions.rs")).unwrap()
+    }
+
+    #[test]
+    fn register_adds_the_module_and_its_entry() {
+        let source = register(REGISTRY, 21).unwrap();
+        let lines = source.lines().collect::<Vec<_>>();
+        let last_mod = lines
+            .iter()
+            .rposition(|line| line.starts_with("pub mod day_"))
+            .unwrap();
+        assert_eq!(lines[last_mod], "pub mod day_twenty_one;");
+        let end = lines.iter().position(|&line| line == "];").unwrap();
+        assert_eq!(
+            lines[end - 1],
+            "    Day::new::<day_twenty_one::DayTwentyOne>(21),"
+        );
+        assert_eq!(lines.len(), REGISTRY.lines().count() + 2);
+
+        assert_eq!(register("pub mod day_one;\n", 2), None);
+    }
+
+    #[test]
+    fn scaffold_a_new_day() {
+        let temp = TempDir::new("scaffold-new");
+        let config = repository(&temp);
+        let real = config.input_path(21, &Dataset::Real);
+        fs::write(&real, "fetched earlier").unwrap();
+
+        let changes = scaffold(temp.path(), &config, 21).unwrap();
+        let source = source_path(temp.path(), 21);
+        let example = config.input_path(21, &Dataset::default_example());
+        assert_eq!(
+            changes,
+            [
+                (Change::Created, source.clone()),
+                (
+                    Change::Updated,
+                    temp.path().join("src").join("solutions.rs")
+                ),
+                (Change::Created, example.clone()),
+                (Change::Kept, real.clone()),
+                (Change::Updated, config.answers_path()),
+            ]
+        );
+        let code = fs::read_to_string(&source).unwrap();
+        assert!(code.contains("pub struct DayTwentyOne {"));
+        assert!(code.contains("read_data(21, &Dataset::default_example())"));
+        assert!(registry(&temp).contains("pub mod day_twenty_one;"));
+        assert_eq!(fs::read_to_string(&example).unwrap(), "");
+        assert_eq!(fs::read_to_string(&real).unwrap(), "fetched earlier");
+        assert_eq!(
+            fs::read_to_string(config.answers_path()).unwrap(),
+            "9 1 real 42\n\
+             # 21 1 real <answer>\n\
+             # 21 2 real <answer>\n\
+             # 21 1 test <answer>\n\
+             # 21 2 test <answer>\n"
+        );
+    }
+
+    #[test]
+    fn refuses_a_registered_day() {
+        let temp = TempDir::new("scaffold-registered");
+        let config = repository(&temp);
+        let result = scaffold(temp.path(), &config, 9);
+        assert!(
+            matches!(&result, Err(Error::Refused(m)) if m.contains("day 9 is already registered")),
+            "{:?}",
+            result
+        );
+        assert_eq!(registry(&temp), REGISTRY);
+        assert!(!source_path(temp.path(), 9).exists());
+        assert!(!config.input_path(9, &Dataset::Real).exists());
+    }
+
+    #[test]
+    fn refuses_to_overwrite_a_module() {
+        let temp = TempDir::new("scaffold-existing");
+        let config = repository(&temp);
+        let source = source_path(temp.path(), 21);
+        fs::write(&source, "// work in progress").unwrap();
+
+        let result = scaffold(temp.path(), &config, 21);
+        assert!(
+            matches!(&result, Err(Error::Refused(m)) if m.ends_with("already exists")),
+            "{:?}",
+            result
+        );
+        assert_eq!(fs::read_to_string(&source).unwrap(), "// work in progress");
+        assert_eq!(registry(&temp), REGISTRY);
+        assert_eq!(
+            fs::read_to_string(config.answers_path()).unwrap(),
+            "9 1 real 42"
+        );
+    }
+}
@@ -6,4 +6,193 @@ pub mod day_seven;
 pub mod day_six;
 pub mod day_three;
 pub mod day_two;
-pub mod day_nine;
\ No newline at end of file
+pub mod day_nine;
+
+use crate::error::Result;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Part {
+    One,
+    Two,
+}
+impl Part {
+    /// The part's number, as written on the command line and in data files.
+    pub fn number(&self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+/// The answer to one part of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    /// Answers drawn as several lines of output, e.g. letters on a grid.
+    Lines(Vec<String>),
+}
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Lines(lines) => write!(f, "{}", lines.join("\n")),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(n: $t) -> Self {
+                Answer::Number(i64::try_from(n).expect("answer does not fit in an i64"))
+            }
+        })*
+    };
+}
+answer_from_int!(i32, u32, i64, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+/// A day's puzzle. `parse` turns the raw puzzle input into whatever the day
+/// needs, and each part computes its answer from that parsed input.
+pub trait Solution: Send + Sync {
+    fn parse(input: &str) -> Result<Self>
+    where
+        Self: Sized;
+    fn part_one(&self) -> Answer;
+    fn part_two(&self) -> Answer;
+
+    fn solve(&self, part: Part) -> Answer {
+        match part {
+            Part::One => self.part_one(),
+            Part::Two => self.part_two(),
+        }
+    }
+}
+
+/// An entry in the registry of implemented days.
+pub struct Day {
+    pub number: u32,
+    parse: fn(&str) -> Result<Box<dyn Solution>>,
+}
+impl Day {
+    pub(crate) const fn new<S: Solution + 'static>(number: u32) -> Day {
+        Day {
+            number,
+            parse: parse_boxed::<S>,
+        }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Box<dyn Solution>> {
+        (self.parse)(input).map_err(|e| e.in_day(self.number))
+    }
+
+    /// Parses `input` and computes the answer to `part`.
+    pub fn solve(&self, input: &str, part: Part) -> Result<Answer> {
+        Ok(self.parse(input)?.solve(part))
+    }
+}
+
+fn parse_boxed<S: Solution + 'static>(input: &str) -> Result<Box<dyn Solution>> {
+    Ok(Box::new(S::parse(input)?))
+}
+
+pub const DAYS: &[Day] = &[
+    Day::new::<day_one::DayOne>(1),
+    Day::new::<day_two::DayTwo>(2),
+    Day::new::<day_three::DayThree>(3),
+    Day::new::<day_four::DayFour>(4),
+    Day::new::<day_five::DayFive>(5),
+    Day::new::<day_six::DaySix>(6),
+    Day::new::<day_seven::DaySeven>(7),
+    Day::new::<day_eight::DayEight>(8),
+    Day::new::<day_nine::DayNine>(9),
+];
+
+pub fn get(day: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.number == day)
+}
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+/// The words for `day`, e.g. `["twenty", "one"]` for 21.
+fn words(day: u32) -> Vec<&'static str> {
+    match day {
+        0..=19 => Vec::from([ONES[day as usize]]),
+        20 => Vec::from(["twenty"]),
+        _ => Vec::from(["twenty", ONES[day as usize - 20]]),
+    }
+}
+
+/// The name of the module holding `day`'s solution, e.g. `day_twenty_one`.
+pub fn module_name(day: u32) -> String {
+    format!("day_{}", words(day).join("_"))
+}
+
+/// The name of the type holding `day`'s solution, e.g. `DayTwentyOne`.
+pub fn type_name(day: u32) -> String {
+    let mut name = String::from("Day");
+    for word in words(day) {
+        name.push_str(&word[..1].to_uppercase());
+        name.push_str(&word[1..]);
+    }
+    name
+}
+
+/// A day's checked-in data file, for tests.
+#[cfg(test)]
+pub(crate) fn read_data(day: u32, dataset: &crate::utils::Dataset) -> String {
+    let path = crate::config::Config::default().input_path(day, dataset);
+    crate::utils::read_file_as_string(path).expect("missing data file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(module_name(7), "day_seven");
+        assert_eq!(module_name(20), "day_twenty");
+        assert_eq!(type_name(21), "DayTwentyOne");
+        for day in DAYS {
+            let source = format!("src/solutions/{}.rs", module_name(day.number));
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(&source);
+            assert!(path.is_file(), "no {} for day {}", source, day.number);
+        }
+    }
+}
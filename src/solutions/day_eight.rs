@@ -1,7 +1,10 @@
-use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::utils;
+use crate::error::{Error, Result};
+use crate::solutions::{Answer, Solution};
+use crate::utils::parse::{self, Pattern};
+use crate::warn;
+use regex::Captures;
 
 #[derive(Hash, Eq, Ord, PartialEq, PartialOrd)]
 enum Direction {
@@ -9,6 +12,8 @@ enum Direction {
     Right,
 }
 
+static NODE: Pattern = Pattern::new(r"^\s*(\w{3})\s*=\s*\((\w{3}),\s*(\w{3})\)\s*$");
+
 #[derive(Clone, Debug)]
 struct Node {
     source: String,
@@ -16,35 +21,96 @@ struct Node {
     right: String,
 }
 impl Node {
-    fn parse(input: &str) -> Node {
-        let re =
-            Regex::new(r"(?<source>\w{3})\s+=\s+\((?<left>\w{3}),\s+(?<right>\w{3})\)").unwrap();
-        let caps = re.captures(input).unwrap();
-        Node {
-            source: caps.name("source").unwrap().as_str().to_string(),
-            left: caps.name("left").unwrap().as_str().to_string(),
-            right: caps.name("right").unwrap().as_str().to_string(),
+    /// Parses the node lines of `input`, checking that every node they lead
+    /// to is one of them.
+    fn parse_all(input: &str, lines: &str) -> Result<Vec<Node>> {
+        let captures = lines
+            .lines()
+            .map(|line| NODE.captures(input, line, "<node> = (<left>, <right>)"))
+            .collect::<Result<Vec<Captures>>>()?;
+        let defined = captures
+            .iter()
+            .map(|caps| &caps[1])
+            .collect::<HashSet<&str>>();
+        for caps in &captures {
+            for next in [caps.get(2), caps.get(3)].into_iter().flatten() {
+                if !defined.contains(next.as_str()) {
+                    return Err(Error::at(
+                        input,
+                        next.as_str(),
+                        format!("undefined node `{}`", next.as_str()),
+                    ));
+                }
+            }
         }
+        Ok(captures
+            .iter()
+            .map(|caps| Node {
+                source: caps[1].to_string(),
+                left: caps[2].to_string(),
+                right: caps[3].to_string(),
+            })
+            .collect())
     }
 }
 
-pub fn run(test: bool) {
-    let read_day_as_string = &utils::read_day_as_string(8, test);
-    let input = read_day_as_string.split("\n\n").collect::<Vec<&str>>();
-    let left_right_instructions = &input[0]
-        .chars()
-        .map(|d| match d {
-            'L' => Direction::Left,
-            'R' => Direction::Right,
-            _ => panic!("Invalid direction!: {}", d),
+pub struct DayEight {
+    left_right_instructions: Vec<Direction>,
+    nodes: Vec<Node>,
+}
+impl Solution for DayEight {
+    fn parse(input: &str) -> Result<Self> {
+        let (instructions, nodes) = match parse::blocks(input)[..] {
+            [instructions, nodes] => (instructions, nodes),
+            _ => {
+                return Err(Error::parse(
+                    1,
+                    1,
+                    "expected instructions and nodes separated by a blank line",
+                ))
+            }
+        };
+        let left_right_instructions = instructions
+            .char_indices()
+            .map(|(i, d)| match d {
+                'L' => Ok(Direction::Left),
+                'R' => Ok(Direction::Right),
+                _ => Err(Error::at(
+                    input,
+                    &instructions[i..],
+                    format!("invalid direction `{}`", d),
+                )),
+            })
+            .collect::<Result<Vec<Direction>>>()?;
+        let parsed = Node::parse_all(input, nodes)?;
+        if !parsed.iter().any(|n| n.source.ends_with('A')) {
+            return Err(Error::at(
+                input,
+                nodes,
+                "expected a start node ending in `A`",
+            ));
+        }
+        Ok(DayEight {
+            left_right_instructions,
+            nodes: parsed,
         })
-        .collect::<Vec<Direction>>();
-    let nodes = &input[1].split('\n').map(Node::parse).collect::<Vec<Node>>();
-    println!("Part 1: {}", part_one(left_right_instructions, nodes));
-    println!("Part 2: {}", part_two(left_right_instructions, nodes));
+    }
+
+    fn part_one(&self) -> Answer {
+        part_one(&self.left_right_instructions, &self.nodes).into()
+    }
+
+    fn part_two(&self) -> Answer {
+        part_two(&self.left_right_instructions, &self.nodes).into()
+    }
 }
 
 fn part_one(left_right_instructions: &[Direction], nodes: &[Node]) -> usize {
+    // Inputs made for part two may start elsewhere.
+    if !nodes.iter().any(|n| n.source == "AAA") {
+        warn!("there is no node AAA to start part one from");
+        return 0;
+    }
     let mut source = "AAA";
     let dest = "ZZZ";
     let mut directions = left_right_instructions.iter().cycle();
@@ -61,8 +127,8 @@ fn part_one(left_right_instructions: &[Direction], nodes: &[Node]) -> usize {
 
         let new_source = *node_cache
             .get(&(&source.to_string(), directions.next().unwrap()))
-            .unwrap();
-        source = &new_source.as_str();
+            .expect("nodes are checked in parse");
+        source = new_source.as_str();
     }
     num_steps
 }
@@ -84,15 +150,15 @@ fn part_two(left_right_instructions: &[Direction], nodes: &[Node]) -> usize {
             while !source.ends_with('Z') {
                 num_steps += 1;
                 let new_source = *node_cache
-                    .get(&(&source, directions.next().unwrap()))
-                    .unwrap();
-                source = &new_source;
+                    .get(&(source, directions.next().unwrap()))
+                    .expect("nodes are checked in parse");
+                source = new_source;
             }
             num_steps
         })
         .collect::<Vec<usize>>();
 
-    finals.iter().fold(finals[0], |acc, f| lcm(acc, *f))
+    finals.iter().fold(1, |acc, f| lcm(acc, *f))
 }
 
 fn lcm(a: usize, b: usize) -> usize {
@@ -106,3 +172,47 @@ fn gcd(a: usize, b: usize) -> usize {
         gcd(b, a % b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> String {
+        match DayEight::parse(input) {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("parsed {:?}", input),
+        }
+    }
+
+    #[test]
+    fn examples() {
+        let day = DayEight::parse("LLR\n\nAAA = (BBB, BBB)\nBBB = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)\n")
+            .unwrap();
+        assert_eq!(day.part_one(), Answer::Number(6));
+        assert_eq!(day.part_two(), Answer::Number(6));
+    }
+
+    #[test]
+    fn rejects_undefined_nodes() {
+        assert_eq!(
+            error("LR\n\nAAA = (BBB, ZZZ)\nZZZ = (ZZZ, ZZZ)\n"),
+            "line 3, column 8: undefined node `BBB`"
+        );
+        assert_eq!(
+            error("LR\n\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, QQQ)\n"),
+            "line 4, column 13: undefined node `QQQ`"
+        );
+    }
+
+    #[test]
+    fn needs_a_start_node() {
+        assert_eq!(
+            error("LR\n\nBBB = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n"),
+            "line 3, column 1: expected a start node ending in `A`"
+        );
+        // Part two's starts are enough to parse, and part one has no answer.
+        let day = DayEight::parse("L\n\n11A = (11Z, 11Z)\n11Z = (11Z, 11Z)\n").unwrap();
+        assert_eq!(day.part_one(), Answer::Number(0));
+        assert_eq!(day.part_two(), Answer::Number(1));
+    }
+}
@@ -1,149 +1,268 @@
-use crate::utils;
-use regex::Regex;
-use std::collections::VecDeque;
+use crate::error::{Error, Result};
+use crate::log::{self, Level};
+use crate::solutions::{Answer, Solution};
+use crate::utils::parse;
+use crate::utils::ranges::{Interval, RangeSet};
+use crate::{debug, trace};
+use std::fmt;
 
-#[derive(Debug, Clone)]
-struct Range(usize, usize);
-impl Range {
-    fn intersect(&self, other: &Range) -> Option<Range> {
-        let disjoint = (self.0 < other.0 && self.1 <= other.0) || (self.0 >= other.1);
-        let self_contains_other = self.0 <= other.0 && self.1 >= other.1;
-        let other_contains_self = other.0 <= self.0 && self.1 <= other.1;
-
-        if disjoint {
-            None
-        } else if self_contains_other {
-            Some(other.clone())
-        } else if other_contains_self {
-            Some(self.clone())
-        } else if self.0 < other.0 {
-            Some(Range(other.0, self.1))
-        } else {
-            Some(Range(self.0, other.1))
-        }
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct Mapping {
-    source_range: Range,
-    destination_range: Range,
+    source: Interval<usize>,
+    destination: usize,
 }
 impl Mapping {
-    fn get_destination(&self, source: usize) -> Option<usize> {
-        match source >= self.source_range.0 && source < self.source_range.1 {
-            true => {
-                let source_offset = source - self.source_range.0;
-                Some(source_offset + self.destination_range.0)
-            }
-            false => None,
-        }
+    /// Where `source` lands, assuming it is in the source range.
+    fn translate(&self, source: usize) -> usize {
+        source - self.source.start + self.destination
+    }
+
+    /// The values the source range lands on.
+    fn destinations(&self) -> Interval<usize> {
+        Interval::with_len(self.destination, self.source.len())
     }
 
-    fn parse(input: &str) -> Mapping {
-        let parts = input.split(' ').collect::<Vec<&str>>();
-        assert_eq!(parts.len(), 3);
+    fn is_identity(&self) -> bool {
+        self.destination == self.source.start
+    }
 
-        let source_range_start = parts[1].parse::<usize>().unwrap();
-        let destination_range_start = parts[0].parse::<usize>().unwrap();
-        let range_length = parts[2].parse::<usize>().unwrap();
+    /// Parses one `<destination> <source> <length>` line of the almanac `input`.
+    fn parse(input: &str, line: &str) -> Result<Mapping> {
+        let [destination_range_start, source_range_start, range_length] =
+            <[usize; 3]>::try_from(parse::integers::<usize>(input, line)?).map_err(|_| {
+                Error::at(
+                    input,
+                    line,
+                    "expected `<destination start> <source start> <length>`",
+                )
+            })?;
 
-        Mapping {
-            source_range: Range(source_range_start, source_range_start + range_length),
-            destination_range: Range(
-                destination_range_start,
-                destination_range_start + range_length,
-            ),
-        }
+        Ok(Mapping {
+            source: Interval::with_len(source_range_start, range_length),
+            destination: destination_range_start,
+        })
+    }
+}
+impl fmt::Display for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.destination,
+            self.source.start,
+            self.source.len()
+        )
     }
 }
 
-#[derive(Debug)]
+/// A piecewise-linear function from one category to the next. Its mappings
+/// are sorted, don't overlap, and never map a value to itself; values that no
+/// mapping covers keep their number.
+#[derive(Debug, Clone)]
 struct Map {
+    from: String,
+    to: String,
     mappings: Vec<Mapping>,
 }
 impl Map {
-    fn parse(input: &str) -> Map {
-        let mut lines = input.split('\n').collect::<VecDeque<&str>>();
-        lines.pop_front();
-        let mappings = lines
+    /// Builds the map from `mappings` as listed in the almanac, where the
+    /// first mapping to cover a value wins.
+    fn new(from: String, to: String, mappings: Vec<Mapping>) -> Map {
+        let mut covered = RangeSet::new();
+        let mut pieces = Vec::new();
+        for mapping in mappings {
+            for part in RangeSet::from(mapping.source).difference(&covered).iter() {
+                pieces.push(Mapping {
+                    source: *part,
+                    destination: mapping.translate(part.start),
+                });
+            }
+            covered.insert(mapping.source);
+        }
+        pieces.sort_by_key(|m| m.source.start);
+
+        // Neighbouring pieces that shift by the same amount are one piece.
+        let mut merged: Vec<Mapping> = Vec::new();
+        for piece in pieces.into_iter().filter(|m| !m.is_identity()) {
+            match merged.last_mut() {
+                Some(last)
+                    if last.source.end == piece.source.start
+                        && last.destinations().end == piece.destination =>
+                {
+                    last.source.end = piece.source.end
+                }
+                _ => merged.push(piece),
+            }
+        }
+        Map {
+            from,
+            to,
+            mappings: merged,
+        }
+    }
+
+    /// Parses one `<from>-to-<to> map:` section of the almanac `input`.
+    fn parse(input: &str, section: &str) -> Result<Map> {
+        let record = parse::record(input, section)?;
+        let (from, to) = record
+            .label
+            .strip_suffix(" map")
+            .and_then(|name| name.split_once("-to-"))
+            .ok_or_else(|| Error::at(input, record.label, "expected `<from>-to-<to> map:`"))?;
+        let mappings = record
+            .lines
             .iter()
-            .map(|l| Mapping::parse(l))
-            .collect::<Vec<Mapping>>();
-        Map { mappings }
+            .map(|l| Mapping::parse(input, l))
+            .collect::<Result<Vec<Mapping>>>()?;
+        Ok(Map::new(from.to_string(), to.to_string(), mappings))
     }
 
     fn map(&self, source: usize) -> usize {
-        for mapping in &self.mappings {
-            let destination = mapping.get_destination(source);
-            match destination {
-                None => continue,
-                Some(destination) => return destination,
-            }
+        let after = self.mappings.partition_point(|m| m.source.start <= source);
+        match after.checked_sub(1).map(|i| &self.mappings[i]) {
+            Some(mapping) if mapping.source.contains(source) => mapping.translate(source),
+            _ => source,
         }
-        source
-    }
-
-    fn map_range(&self, range: &Range) -> Vec<Range> {
-        let mut result = Vec::new();
-        let mut to_map = Vec::from([range.clone()]);
-        while let Some(range) = to_map.pop() {
-            let mut mapped = false;
-            for mapping in &self.mappings {
-                let intersection = mapping.source_range.intersect(&range);
-                match intersection {
-                    None => continue,
-                    Some(intersection) => {
-                        println!(
-                            "Found intersection between {:?} and {:?}: {:?}",
-                            intersection, mapping.source_range, range
-                        );
-                        let destination = mapping.get_destination(intersection.0).unwrap();
-                        let length = intersection.1 - intersection.0;
-                        result.push(Range(destination, destination + length));
-                        mapped = true;
-
-                        if range.0 < mapping.source_range.0 {
-                            let new_left_range = Range(range.0, mapping.source_range.0);
-                            println!("Adding new left range: {:?}", new_left_range);
-                            to_map.push(new_left_range);
-                        }
-                        if range.1 > mapping.source_range.1 {
-                            let new_right_range = Range(mapping.source_range.1, range.1);
-                            println!("Adding new right range: {:?}", new_right_range);
-                            to_map.push(new_right_range);
-                        }
-                        break;
-                    }
+    }
+
+    /// Splits `interval` where the map's pieces start and end, pairing each
+    /// part with where its first value lands.
+    fn segments(&self, interval: Interval<usize>) -> Vec<(Interval<usize>, usize)> {
+        let first = self
+            .mappings
+            .partition_point(|m| m.source.end <= interval.start);
+        let mut pieces = self.mappings[first..].iter().peekable();
+        let mut segments = Vec::new();
+        let mut start = interval.start;
+        while start < interval.end {
+            let (end, destination) = match pieces.peek() {
+                Some(mapping) if mapping.source.start <= start => {
+                    let end = mapping.source.end.min(interval.end);
+                    let destination = mapping.translate(start);
+                    pieces.next();
+                    (end, destination)
                 }
+                Some(mapping) => (mapping.source.start.min(interval.end), start),
+                None => (interval.end, start),
+            };
+            segments.push((Interval::new(start, end), destination));
+            start = end;
+        }
+        segments
+    }
+
+    /// Where every value of `sources` lands.
+    fn map_ranges(&self, sources: &RangeSet<usize>) -> RangeSet<usize> {
+        sources
+            .iter()
+            .flat_map(|interval| self.segments(*interval))
+            .map(|(segment, destination)| {
+                trace!("{} lands at {}", segment, destination);
+                Interval::with_len(destination, segment.len())
+            })
+            .collect()
+    }
+
+    /// Every value that lands on `destination`, in order. A value no mapping
+    /// covers lands on itself, while one that is covered never does.
+    fn unmap(&self, destination: usize) -> Vec<usize> {
+        let mut sources = self
+            .mappings
+            .iter()
+            .filter(|m| m.destinations().contains(destination))
+            .map(|m| m.source.start + (destination - m.destination))
+            .collect::<Vec<usize>>();
+        if self.map(destination) == destination {
+            sources.push(destination);
+        }
+        sources.sort_unstable();
+        sources
+    }
+
+    /// Every value that lands in `destinations`.
+    fn unmap_ranges(&self, destinations: &RangeSet<usize>) -> RangeSet<usize> {
+        let covered = self
+            .mappings
+            .iter()
+            .map(|m| m.source)
+            .collect::<RangeSet<usize>>();
+        let mut sources = destinations.difference(&covered);
+        for mapping in &self.mappings {
+            let landing = destinations.intersection(&RangeSet::from(mapping.destinations()));
+            for part in landing.iter() {
+                sources.insert(Interval::with_len(
+                    mapping.source.start + (part.start - mapping.destination),
+                    part.len(),
+                ));
             }
+        }
+        sources
+    }
 
-            if !mapped {
-                result.push(range.clone());
+    /// The map that does this one and then `next`.
+    fn compose(&self, next: &Map) -> Map {
+        let mut mappings = Vec::new();
+        for (segment, destination) in self.segments(Interval::new(0, usize::MAX)) {
+            let image = Interval::with_len(destination, segment.len());
+            for (part, end_destination) in next.segments(image) {
+                mappings.push(Mapping {
+                    source: Interval::with_len(
+                        segment.start + (part.start - image.start),
+                        part.len(),
+                    ),
+                    destination: end_destination,
+                });
             }
         }
-        result
+        Map::new(self.from.clone(), next.to.clone(), mappings)
+    }
+}
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-to-{} map:", self.from, self.to)?;
+        for mapping in &self.mappings {
+            write!(f, "\n{}", mapping)?;
+        }
+        Ok(())
     }
 }
 
-pub fn run(test_mode: bool) {
-    let input = utils::read_day_as_string(5, test_mode);
-    let mut parts = input
-        .split("\n\n")
-        .map(|p| p.trim())
-        .collect::<VecDeque<&str>>();
-
-    let seed_line = parts.pop_front().unwrap();
-    let seeds_as_strs = seed_line.split(": ").collect::<Vec<&str>>()[1];
-    let seeds = seeds_as_strs
-        .split(' ')
-        .map(|s| s.parse::<usize>().unwrap())
-        .collect::<Vec<usize>>();
+pub struct DayFive {
+    seeds: Vec<usize>,
+    maps: Vec<Map>,
+}
+impl Solution for DayFive {
+    fn parse(input: &str) -> Result<Self> {
+        let sections = parse::blocks(input);
+        let seed_block = sections.first().copied().unwrap_or(input);
+        let seeds = match parse::record(input, seed_block)? {
+            parse::Record {
+                label: "seeds",
+                value,
+                ..
+            } => parse::integers::<usize>(input, value)?,
+            _ => return Err(Error::at(input, seed_block, "expected `seeds: <numbers>`")),
+        };
+        if seeds.is_empty() {
+            return Err(Error::at(input, seed_block, "expected at least one seed"));
+        }
+
+        let maps = sections
+            .iter()
+            .skip(1)
+            .map(|p| Map::parse(input, p))
+            .collect::<Result<Vec<Map>>>()?;
 
-    let maps = parts.iter().map(|p| Map::parse(p)).collect::<Vec<Map>>();
+        Ok(DayFive { seeds, maps })
+    }
 
-    println!("Part one: {}", part_one(&seeds, &maps));
-    println!("Part two: {}", part_two(seeds_as_strs, &maps));
+    fn part_one(&self) -> Answer {
+        part_one(&self.seeds, &self.maps).into()
+    }
+
+    fn part_two(&self) -> Answer {
+        part_two(&self.seeds, &self.maps).into()
+    }
 }
 
 fn part_one(seeds: &[usize], maps: &[Map]) -> usize {
@@ -160,33 +279,277 @@ fn part_one(seeds: &[usize], maps: &[Map]) -> usize {
         .unwrap()
 }
 
-fn part_two(seeds_as_strs: &str, maps: &[Map]) -> usize {
-    let re = Regex::new(r"((?<range_start>\d+) (?<range_length>\d+))+").unwrap();
-
-    let mut ranges = re
-        .captures_iter(seeds_as_strs)
-        .map(|c| {
-            let range_start = c
-                .name("range_start")
-                .unwrap()
-                .as_str()
-                .parse::<usize>()
-                .unwrap();
-            let range_length = c
-                .name("range_length")
-                .unwrap()
-                .as_str()
-                .parse::<usize>()
-                .unwrap();
-            Range(range_start, range_start + range_length)
-        })
-        .collect::<Vec<Range>>();
+fn part_two(seeds: &[usize], maps: &[Map]) -> usize {
+    let seeds = seeds
+        .chunks_exact(2)
+        .map(|pair| Interval::with_len(pair[0], pair[1]))
+        .collect::<RangeSet<usize>>();
 
-    for map in maps {
-        ranges = ranges
+    let locations = match maps
+        .iter()
+        .cloned()
+        .reduce(|chain, map| chain.compose(&map))
+    {
+        Some(chain) => {
+            debug!("{}", chain);
+            let locations = chain.map_ranges(&seeds);
+            if let Some(&lowest) = locations.iter().next() {
+                trace_seeds(&seeds, &chain, lowest);
+            }
+            locations
+        }
+        None => seeds,
+    };
+    locations.min().unwrap_or(0)
+}
+
+/// Traces which seeds lead to the `lowest` locations.
+fn trace_seeds(seeds: &RangeSet<usize>, chain: &Map, lowest: Interval<usize>) {
+    if !log::enabled(Level::Trace, module_path!()) {
+        return;
+    }
+    let sources = chain
+        .unmap(lowest.start)
+        .into_iter()
+        .filter(|&seed| seeds.contains(seed))
+        .collect::<Vec<usize>>();
+    trace!(
+        "location {} comes from seeds {:?}, and locations {} from seeds {}",
+        lowest.start,
+        sources,
+        lowest,
+        chain
+            .unmap_ranges(&RangeSet::from(lowest))
+            .intersection(seeds)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions;
+    use crate::utils::Dataset;
+
+    fn almanacs() -> Vec<DayFive> {
+        [Dataset::default_example(), Dataset::Real]
+            .iter()
+            .map(|dataset| DayFive::parse(&solutions::read_data(5, dataset)).unwrap())
+            .collect()
+    }
+
+    fn compose_all(maps: &[Map]) -> Map {
+        maps.iter()
+            .cloned()
+            .reduce(|chain, map| chain.compose(&map))
+            .unwrap()
+    }
+
+    fn map(section: &str) -> Map {
+        Map::parse(section, section).unwrap()
+    }
+
+    /// Values either side of `value`.
+    fn around(value: usize) -> impl Iterator<Item = usize> {
+        value.saturating_sub(2)..=value.saturating_add(2)
+    }
+
+    #[test]
+    fn chain_agrees_with_maps_one_after_another() {
+        for almanac in almanacs() {
+            let maps = &almanac.maps;
+            let chain = compose_all(maps);
+            let step_by_step = |seed: usize| maps.iter().fold(seed, |value, map| map.map(value));
+
+            // Where each map's pieces start and end, pulled back to seeds, is
+            // where the chain may change pieces or merge them.
+            let mut breakpoints = Vec::from([0]);
+            for (i, map) in maps.iter().enumerate() {
+                for mapping in &map.mappings {
+                    for value in [mapping.source.start, mapping.source.end] {
+                        breakpoints.push(value);
+                        let seeds = match i {
+                            0 => Vec::from([value]),
+                            _ => compose_all(&maps[..i]).unmap(value),
+                        };
+                        breakpoints.extend(seeds);
+                    }
+                }
+            }
+            for mapping in &chain.mappings {
+                breakpoints.extend([mapping.source.start, mapping.source.end]);
+            }
+            for seed in breakpoints.into_iter().flat_map(around) {
+                assert_eq!(chain.map(seed), step_by_step(seed), "seed {}", seed);
+            }
+        }
+    }
+
+    /// The lowest location any of `seeds` leads to, worked out backwards: it
+    /// is one less than the smallest `n` for which some seed leads to a
+    /// location below `n`.
+    fn lowest_location(seeds: &RangeSet<usize>, chain: &Map) -> Option<usize> {
+        let leads_below = |n: usize| {
+            let locations = RangeSet::from(Interval::new(0, n));
+            !chain
+                .unmap_ranges(&locations)
+                .intersection(seeds)
+                .is_empty()
+        };
+        if !leads_below(usize::MAX) {
+            return None;
+        }
+        // Nothing leads below `low`, something leads below `high`.
+        let (mut low, mut high) = (0, usize::MAX);
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            match leads_below(middle) {
+                true => high = middle,
+                false => low = middle,
+            }
+        }
+        let location = high - 1;
+        assert!(chain
+            .unmap(location)
             .iter()
-            .flat_map(|r| map.map_range(r))
-            .collect::<Vec<Range>>();
+            .any(|&seed| seeds.contains(seed)));
+        Some(location)
+    }
+
+    fn seed_ranges(seeds: &[usize]) -> RangeSet<usize> {
+        seeds
+            .chunks_exact(2)
+            .map(|pair| Interval::with_len(pair[0], pair[1]))
+            .collect()
+    }
+
+    #[test]
+    fn part_two_agrees_with_search_from_locations() {
+        for almanac in almanacs() {
+            let chain = compose_all(&almanac.maps);
+            assert_eq!(
+                lowest_location(&seed_ranges(&almanac.seeds), &chain),
+                Some(part_two(&almanac.seeds, &almanac.maps))
+            );
+        }
+    }
+
+    #[test]
+    fn seeds_leading_to_low_locations() {
+        let example =
+            DayFive::parse(&solutions::read_data(5, &Dataset::default_example())).unwrap();
+        let chain = compose_all(&example.maps);
+        let seeds = seed_ranges(&example.seeds);
+        let below = |n| {
+            chain
+                .unmap_ranges(&RangeSet::from(Interval::new(0, n)))
+                .intersection(&seeds)
+        };
+        assert!(below(46).is_empty());
+        assert_eq!(below(47), RangeSet::from(Interval::new(82, 83)));
+        assert_eq!(chain.unmap(46), [82]);
+    }
+
+    #[test]
+    fn unmap_undoes_map() {
+        for almanac in almanacs() {
+            let chain = compose_all(&almanac.maps);
+            for map in almanac.maps.iter().chain([&chain]) {
+                let values = map
+                    .mappings
+                    .iter()
+                    .flat_map(|m| [m.source.start, m.source.end, m.destination])
+                    .flat_map(around);
+                for value in values {
+                    let destination = map.map(value);
+                    let sources = map.unmap(destination);
+                    assert!(sources.contains(&value), "{} in {}", value, map);
+                    assert!(sources.iter().all(|&s| map.map(s) == destination));
+                    assert!(sources.windows(2).all(|pair| pair[0] < pair[1]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unmap_ranges_undoes_map_ranges() {
+        for almanac in almanacs() {
+            let chain = compose_all(&almanac.maps);
+            let seeds = seed_ranges(&almanac.seeds);
+            for map in almanac.maps.iter().chain([&chain]) {
+                // Every seed is among those that land where the seeds land...
+                let destinations = map.map_ranges(&seeds);
+                let sources = map.unmap_ranges(&destinations);
+                assert!(seeds.difference(&sources).is_empty());
+                // ...and whatever lands somewhere lands there.
+                let landed = map.map_ranges(&map.unmap_ranges(&seeds));
+                assert!(landed.difference(&seeds).is_empty());
+                for interval in sources.iter().take(20) {
+                    for value in around(interval.start).chain(around(interval.end)) {
+                        assert_eq!(
+                            sources.contains(value),
+                            destinations.contains(map.map(value)),
+                            "{} in {}",
+                            value,
+                            map
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chain_is_sorted_without_overlaps_or_identities() {
+        for almanac in almanacs() {
+            let chain = compose_all(&almanac.maps);
+            assert_eq!(
+                (chain.from.as_str(), chain.to.as_str()),
+                ("seed", "location")
+            );
+            for pair in chain.mappings.windows(2) {
+                assert!(pair[0].source.end <= pair[1].source.start);
+            }
+            assert!(chain.mappings.iter().all(|m| !m.is_identity()));
+        }
+    }
+
+    #[test]
+    fn new_merges_pieces_that_shift_alike() {
+        let merged = map("a-to-b map:\n10 0 5\n15 5 5\n30 20 5");
+        assert_eq!(merged.to_string(), "a-to-b map:\n10 0 10\n30 20 5");
+        // A later mapping only covers what earlier ones leave.
+        let shadowed = map("a-to-b map:\n10 0 5\n50 3 5");
+        assert_eq!(shadowed.to_string(), "a-to-b map:\n10 0 5\n52 5 3");
+        assert!(map("a-to-b map:\n4 4 10").mappings.is_empty());
+    }
+
+    #[test]
+    fn compose_joins_pieces_that_line_up() {
+        let first = map("a-to-b map:\n100 0 5\n200 5 5");
+        let second = map("b-to-c map:\n1000 100 5\n1005 200 5");
+        let chain = first.compose(&second);
+        // The values 100.. and 200.. keep their numbers through `first`.
+        assert_eq!(
+            chain.to_string(),
+            "a-to-c map:\n1000 0 10\n1000 100 5\n1005 200 5"
+        );
+        for value in 0..20 {
+            assert_eq!(chain.map(value), second.map(first.map(value)));
+        }
+
+        // Pieces that undo each other leave nothing of `first`.
+        let back = map("b-to-a map:\n0 100 5\n5 200 5");
+        assert_eq!(
+            first.compose(&back).to_string(),
+            "a-to-a map:\n0 100 5\n5 200 5"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_seeds() {
+        for input in ["seeds:\n\na-to-b map:\n1 2 3\n", "seeds: none\n", ""] {
+            let error = DayFive::parse(input).err().expect("parsed without seeds");
+            assert!(error.to_string().contains("line 1"), "{}", error);
+        }
     }
-    ranges.iter().map(|r| r.0).min().unwrap_or(0)
 }
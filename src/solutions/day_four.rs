@@ -1,5 +1,7 @@
-use crate::utils;
-use regex::Regex;
+use crate::error::{Error, Result};
+use crate::solutions::{Answer, Solution};
+use crate::utils::{self, parse};
+use crate::{debug, trace};
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug)]
@@ -9,29 +11,17 @@ struct Card {
     chosen_numbers: HashSet<usize>,
 }
 impl Card {
-    fn parse(s: &str) -> Card {
-        let re = Regex::new(r"Card\s+(\d+):\s+((\d\s*)+)\s+\|\s+((\d\s*)+)").unwrap();
-        let captures = re.captures(s).unwrap();
-        let mut winning = HashSet::new();
-        let mut chosen = HashSet::new();
+    fn parse(s: &str) -> Result<Card> {
+        let (label, lists) = parse::key_lists::<usize>(s, s, '|')?;
+        let id = parse::labelled_number::<usize>(s, label, "Card")?;
+        let [winning_numbers, chosen_numbers] = <[Vec<usize>; 2]>::try_from(lists)
+            .map_err(|_| Error::at(s, s, "expected `Card <id>: <numbers> | <numbers>`"))?;
 
-        let id = captures[1].parse::<usize>().unwrap();
-        let winning_numbers = captures[2]
-            .split_whitespace()
-            .map(|n| n.parse::<usize>().unwrap())
-            .collect::<Vec<usize>>();
-        let chosen_numbers = captures[4]
-            .split_whitespace()
-            .map(|n| n.parse::<usize>().unwrap())
-            .collect::<Vec<usize>>();
-        winning.extend(winning_numbers);
-        chosen.extend(chosen_numbers);
-
-        Card {
+        Ok(Card {
             id,
-            winning_numbers: winning,
-            chosen_numbers: chosen,
-        }
+            winning_numbers: winning_numbers.into_iter().collect(),
+            chosen_numbers: chosen_numbers.into_iter().collect(),
+        })
     }
 
     fn num_winning(&self) -> usize {
@@ -51,14 +41,22 @@ impl Card {
 }
 
 type CardCounts = HashMap<usize, usize>;
-pub fn run(test_mode: bool) {
-    let lines = utils::read_day_as_lines(4, test_mode);
-    let cards = lines
-        .iter()
-        .map(|line| Card::parse(line))
-        .collect::<Vec<Card>>();
-    println!("Part one: {}", part_one(&cards));
-    println!("Part two: {}", part_two(&cards));
+pub struct DayFour {
+    cards: Vec<Card>,
+}
+impl Solution for DayFour {
+    fn parse(input: &str) -> Result<Self> {
+        let cards = utils::parse_lines(input, Card::parse)?;
+        Ok(DayFour { cards })
+    }
+
+    fn part_one(&self) -> Answer {
+        part_one(&self.cards).into()
+    }
+
+    fn part_two(&self) -> Answer {
+        part_two(&self.cards).into()
+    }
 }
 fn part_one(cards: &[Card]) -> usize {
     cards.iter().map(|c| c.score()).sum()
@@ -68,11 +66,11 @@ fn part_two(cards: &[Card]) -> usize {
     cards.iter().for_each(|card| {
         counts.insert(card.id, 1);
     });
-    println!("Counts before: {:?}", counts);
+    debug!("counts before: {:?}", counts);
     for card in cards.iter() {
         let count = *counts.get(&card.id).unwrap_or(&0);
         let num_winning = card.num_winning();
-        println!("Card id: {}, num_winning: {}", card.id, num_winning);
+        trace!("card {} has {} winning numbers", card.id, num_winning);
         if num_winning > 0 {
             let range = (card.id + 1)..=(card.id + num_winning);
             range.for_each(|id| {
@@ -81,6 +79,6 @@ fn part_two(cards: &[Card]) -> usize {
             });
         }
     }
-    println!("Counts after: {:?}", counts);
+    debug!("counts after: {:?}", counts);
     counts.values().sum()
 }
@@ -1,6 +1,6 @@
-use regex::Regex;
-
-use crate::utils;
+use crate::error::{Error, Result};
+use crate::solutions::{Answer, Solution};
+use crate::utils::{self, parse};
 
 fn compute_difference_sequence(input_sequence: &[i32]) -> Vec<i32> {
 	let differences = input_sequence.iter().enumerate().filter_map(|(i, x)| {
@@ -31,7 +31,7 @@ fn next_element(input_sequence: &Vec<i32>) -> i32 {
 	let sequences = until_zeroes(input_sequence);
 
 	sequences.iter().rfold(0, |acc, x| {
-		acc + x[x.len() - 1]
+		x.last().map_or(acc, |last| acc + last)
 	})
 }
 
@@ -39,28 +39,80 @@ fn previous_element(input_sequence: &Vec<i32>) -> i32 {
 	let sequences = until_zeroes(input_sequence);
 
 	sequences.iter().rfold(0, |acc, x| {
-		x[0] - acc
+		x.first().map_or(acc, |first| first - acc)
 	})
 }
 
 
-fn part_one(inputs: &Vec<Vec<i32>>) -> i32 {
-	inputs.iter().map(|seq| next_element(seq)).sum()
+fn part_one(inputs: &[Vec<i32>]) -> i32 {
+	inputs.iter().map(next_element).sum()
+}
+
+fn part_two(inputs: &[Vec<i32>]) -> i32 {
+	inputs.iter().map(previous_element).sum()
 }
 
-fn part_two(inputs: &Vec<Vec<i32>>) -> i32 {
-	inputs.iter().map(|seq| previous_element(seq)).sum()
+pub struct DayNine {
+	inputs: Vec<Vec<i32>>,
 }
+impl Solution for DayNine {
+	fn parse(input: &str) -> Result<Self> {
+		let inputs = utils::parse_lines(input, |line| {
+			let sequence = parse::integers::<i32>(line, line)?;
+			match sequence.is_empty() {
+				true => Err(Error::at(line, line, "expected a sequence of numbers")),
+				false => Ok(sequence),
+			}
+		})?;
+		Ok(DayNine { inputs })
+	}
 
-pub fn run(test: bool) {
-	let lines = utils::read_day_as_lines(9, test);
-	let re = Regex::new(r"(\-*\d+)").unwrap();
-	let inputs = lines.iter().map(|line| {
-		re.captures_iter(line).map(|cap| {
-			cap[1].parse::<i32>().unwrap()
-		}).collect::<Vec<i32>>()
-	}).collect::<Vec<Vec<i32>>>();
-
-	println!("Part one: {}", part_one(&inputs));
-	println!("Part two: {}", part_two(&inputs));
+	fn part_one(&self) -> Answer {
+		part_one(&self.inputs).into()
+	}
+
+	fn part_two(&self) -> Answer {
+		part_two(&self.inputs).into()
+	}
 } 
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn extrapolates_both_ways() {
+		let inputs = DayNine::parse("0 3 6 9 12 15\n10 13 16 21 30 45\n").unwrap().inputs;
+		assert_eq!(part_one(&inputs), 18 + 68);
+		assert_eq!(part_two(&inputs), -3 + 5);
+		// A single number has no differences, and stays the same either way.
+		assert_eq!(next_element(&vec![7]), 7);
+		assert_eq!(previous_element(&vec![7]), 7);
+	}
+
+	#[test]
+	fn rejects_lines_without_numbers() {
+		for (input, line) in [("1 2\n\n3 4\n", 2), ("1 2\nnone\n", 2), ("\n", 1)] {
+			let error = DayNine::parse(input).err().expect("parsed a line without numbers");
+			assert!(error.to_string().contains(&format!("line {},", line)), "{}", error);
+		}
+	}
+
+	#[test]
+	fn rejects_tokens_that_are_not_integers() {
+		for (input, expected) in [
+			("1 2 x
+", "line 1, column 5: expected an integer"),
+			("0 1
+Game x: 3 blue
+", "line 2, column 1: expected an integer"),
+			("Time: 7 15
+", "line 1, column 1: expected an integer"),
+			("1 2.5 3
+", "line 1, column 3: expected an integer"),
+		] {
+			let error = DayNine::parse(input).err().expect("parsed a token that is not an integer");
+			assert_eq!(error.to_string(), expected);
+		}
+	}
+}
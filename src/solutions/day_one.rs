@@ -1,7 +1,11 @@
-use crate::utils;
+use crate::error::Result;
+use crate::solutions::{Answer, Solution};
+use crate::utils::{self, parse::Pattern};
 use regex::Regex;
 use std::collections::HashMap;
 
+static DIGIT_WORD: Pattern = Pattern::new(r"(one|two|three|four|five|six|seven|eight|nine)");
+
 fn part_one(lines: &[String]) -> i32 {
     let mut lines_as_ints: Vec<Vec<i32>> = Vec::new();
     lines.iter().for_each(|line| {
@@ -22,7 +26,7 @@ fn part_one(lines: &[String]) -> i32 {
     calibration_values.sum()
 }
 
-fn replace_all(s: &String, re: &regex::Regex, lookup: &HashMap<&str, &str>) -> String {
+fn replace_all(s: &String, re: &Regex, lookup: &HashMap<&str, &str>) -> String {
     let mut news = s.to_string();
     loop {
         match re.find(&news) {
@@ -37,30 +41,40 @@ fn replace_all(s: &String, re: &regex::Regex, lookup: &HashMap<&str, &str>) -> S
     }
 }
 
-fn part_two(lines: &[String], re: regex::Regex, lookup: HashMap<&str, &str>) -> i32 {
+fn part_two(lines: &[String], re: &Regex, lookup: HashMap<&str, &str>) -> i32 {
     let transformed = lines
         .iter()
-        .map(|s| replace_all(s, &re, &lookup))
+        .map(|s| replace_all(s, re, &lookup))
         .collect::<Vec<String>>();
     part_one(&transformed)
 }
 
-pub fn run(test_mode: bool) {
-    let re = Regex::new(r"(one|two|three|four|five|six|seven|eight|nine)").unwrap();
-    let lookup: HashMap<&str, &str> = HashMap::from([
-        ("one", "1e"),
-        ("two", "2o"),
-        ("three", "3e"),
-        ("four", "4r"),
-        ("five", "5e"),
-        ("six", "6x"),
-        ("seven", "7n"),
-        ("eight", "8t"),
-        ("nine", "9e"),
-    ]);
+pub struct DayOne {
+    lines: Vec<String>,
+}
+impl Solution for DayOne {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(DayOne {
+            lines: utils::split_lines(input),
+        })
+    }
+
+    fn part_one(&self) -> Answer {
+        part_one(&self.lines).into()
+    }
 
-    let lines = utils::read_day_as_lines(1, test_mode);
-    let test_lines = utils::read_day_as_lines(1, test_mode);
-    println!("Part one: {}", part_one(&lines));
-    println!("Part two: {:?}", part_two(&test_lines, re, lookup));
+    fn part_two(&self) -> Answer {
+        let lookup: HashMap<&str, &str> = HashMap::from([
+            ("one", "1e"),
+            ("two", "2o"),
+            ("three", "3e"),
+            ("four", "4r"),
+            ("five", "5e"),
+            ("six", "6x"),
+            ("seven", "7n"),
+            ("eight", "8t"),
+            ("nine", "9e"),
+        ]);
+        part_two(&self.lines, &DIGIT_WORD, lookup).into()
+    }
 }
@@ -1,5 +1,6 @@
+use crate::error::{Error, Result};
+use crate::solutions::{Answer, Solution};
 use crate::utils;
-use regex::Regex;
 use std::{cmp::Ordering, collections::HashMap, convert::TryInto};
 
 type Cards = [Card; 5];
@@ -21,22 +22,22 @@ enum Card {
     Ace,
 }
 impl Card {
-    fn from_char(c: char) -> Self {
+    fn from_char(c: char) -> Option<Self> {
         match c {
-            'J' => Self::Jack,
-            '2' => Self::Two,
-            '3' => Self::Three,
-            '4' => Self::Four,
-            '5' => Self::Five,
-            '6' => Self::Six,
-            '7' => Self::Seven,
-            '8' => Self::Eight,
-            '9' => Self::Nine,
-            'T' => Self::Ten,
-            'Q' => Self::Queen,
-            'K' => Self::King,
-            'A' => Self::Ace,
-            _ => panic!("Invalid card"),
+            'J' => Some(Self::Jack),
+            '2' => Some(Self::Two),
+            '3' => Some(Self::Three),
+            '4' => Some(Self::Four),
+            '5' => Some(Self::Five),
+            '6' => Some(Self::Six),
+            '7' => Some(Self::Seven),
+            '8' => Some(Self::Eight),
+            '9' => Some(Self::Nine),
+            'T' => Some(Self::Ten),
+            'Q' => Some(Self::Queen),
+            'K' => Some(Self::King),
+            'A' => Some(Self::Ace),
+            _ => None,
         }
     }
 }
@@ -108,6 +109,33 @@ struct Hand {
     alternate_rules: bool,
 }
 impl Hand {
+    fn parse(line: &str) -> Result<Hand> {
+        let (hand, bid) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+            [hand, bid] => (hand, bid),
+            _ => return Err(Error::at(line, line, "expected `<cards> <bid>`")),
+        };
+        let cards = hand
+            .char_indices()
+            .map(|(i, c)| {
+                Card::from_char(c)
+                    .ok_or_else(|| Error::at(line, &hand[i..], format!("invalid card `{}`", c)))
+            })
+            .collect::<Result<Vec<Card>>>()?;
+        let cards: Cards = cards.try_into().map_err(|cards: Vec<Card>| {
+            Error::at(
+                line,
+                hand,
+                format!("expected 5 cards, found {}", cards.len()),
+            )
+        })?;
+        let bid = utils::parse_number::<usize>(line, bid)?;
+        Ok(Hand {
+            bid,
+            cards,
+            alternate_rules: false,
+        })
+    }
+
     fn hand_type(&self) -> HandType {
         match self.alternate_rules {
             true => HandType::from_cards_alternate_rules(&self.cards),
@@ -139,61 +167,37 @@ impl PartialOrd for Hand {
     }
 }
 
-pub fn run(test_mode: bool) {
-    let lines = utils::read_day_as_lines(7, test_mode);
-    println!("Day 7");
-    println!("Part one: {}", part_one(&lines));
-    println!("Part two: {}", part_two(&lines));
+pub struct DaySeven {
+    hands: Vec<Hand>,
 }
-
-pub fn part_one(lines: &[String]) -> usize {
-    let re = Regex::new(r"(?<hand>\w+)\s+(?<bid>\d+)").unwrap();
-    let mut hands = lines
-        .iter()
-        .map(|l| {
-            let caps = re.captures(l).unwrap();
-            let cards: Cards = caps
-                .name("hand")
-                .unwrap()
-                .as_str()
-                .chars()
-                .map(Card::from_char)
-                .collect::<Vec<Card>>()
-                .try_into()
-                .unwrap();
-            let bid = caps.name("bid").unwrap().as_str().parse::<usize>().unwrap();
-            Hand {
-                bid,
-                cards,
-                alternate_rules: false,
-            }
+impl Solution for DaySeven {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(DaySeven {
+            hands: utils::parse_lines(input, Hand::parse)?,
         })
-        .collect::<Vec<Hand>>();
+    }
+
+    fn part_one(&self) -> Answer {
+        part_one(&self.hands).into()
+    }
+
+    fn part_two(&self) -> Answer {
+        part_two(&self.hands).into()
+    }
+}
+
+fn part_one(hands: &[Hand]) -> usize {
+    let mut hands = hands.to_vec();
     hands.sort();
     hands.iter().enumerate().map(|(i, h)| (i + 1) * h.bid).sum()
 }
 
-pub fn part_two(lines: &[String]) -> usize {
-    let re = Regex::new(r"(?<hand>\w+)\s+(?<bid>\d+)").unwrap();
-    let mut hands = lines
+fn part_two(hands: &[Hand]) -> usize {
+    let mut hands = hands
         .iter()
-        .map(|l| {
-            let caps = re.captures(l).unwrap();
-            let cards: Cards = caps
-                .name("hand")
-                .unwrap()
-                .as_str()
-                .chars()
-                .map(Card::from_char)
-                .collect::<Vec<Card>>()
-                .try_into()
-                .unwrap();
-            let bid = caps.name("bid").unwrap().as_str().parse::<usize>().unwrap();
-            Hand {
-                bid,
-                cards,
-                alternate_rules: true,
-            }
+        .map(|h| Hand {
+            alternate_rules: true,
+            ..*h
         })
         .collect::<Vec<Hand>>();
     hands.sort();
@@ -1,5 +1,6 @@
-use crate::utils::*;
-use regex::Regex;
+use crate::error::{Error, Result};
+use crate::solutions::{Answer, Solution};
+use crate::utils::parse;
 
 #[derive(Debug)]
 struct Race {
@@ -14,50 +15,84 @@ impl Race {
     }
 }
 
-pub fn run(test_mode: bool) {
-    let lines = read_day_as_lines(6, test_mode);
-    let times_str = lines.get(0).unwrap();
-    let distances_str = lines.get(1).unwrap();
+/// Parses the numbers on the `<label>: <numbers>` line of `input`, both one
+/// by one and with their digits joined into a single number.
+fn parse_numbers(input: &str, line: &str, label: &str) -> Result<(Vec<usize>, usize)> {
+    let expected = format!("{}: <numbers>", label);
+    let numbers = match parse::key_value(input, line, &expected)? {
+        (key, numbers) if key == label => numbers,
+        _ => return Err(Error::at(input, line, format!("expected `{}`", expected))),
+    };
+    let races = parse::integers(input, numbers)?;
+    let joined = numbers
+        .split_whitespace()
+        .collect::<String>()
+        .parse::<usize>()
+        .map_err(|_| {
+            Error::at(
+                input,
+                line,
+                "expected at least one number that fits in a usize",
+            )
+        })?;
+    Ok((races, joined))
+}
 
-    println!("Part one: {}", part_one(times_str, distances_str));
-    println!("Part two: {}", part_two(times_str, distances_str));
+pub struct DaySix {
+    races: Vec<Race>,
+    race: Race,
 }
+impl Solution for DaySix {
+    fn parse(input: &str) -> Result<Self> {
+        let mut lines = input.lines();
+        let times_str = lines
+            .next()
+            .ok_or_else(|| Error::parse(1, 1, "expected a `Time:` line"))?;
+        let distances_str = lines
+            .next()
+            .ok_or_else(|| Error::parse(2, 1, "expected a `Distance:` line"))?;
+
+        let (times, time) = parse_numbers(input, times_str, "Time")?;
+        let (distances, distance) = parse_numbers(input, distances_str, "Distance")?;
+        if times.len() != distances.len() {
+            return Err(Error::at(
+                input,
+                distances_str,
+                format!(
+                    "expected {} distances, found {}",
+                    times.len(),
+                    distances.len()
+                ),
+            ));
+        }
 
-fn part_one(times_str: &str, distances_str: &str) -> usize {
-    let re = Regex::new(r"\d+").unwrap();
-    let times = re
-        .find_iter(times_str)
-        .map(|t| t.as_str().parse::<usize>().unwrap());
-    let distances = re
-        .find_iter(distances_str)
-        .map(|t| t.as_str().parse::<usize>().unwrap());
-    let races = times
-        .zip(distances)
-        .map(|(t, d)| Race {
-            time: t,
-            distance: d,
+        let races = times
+            .into_iter()
+            .zip(distances)
+            .map(|(t, d)| Race {
+                time: t,
+                distance: d,
+            })
+            .collect::<Vec<Race>>();
+        Ok(DaySix {
+            races,
+            race: Race { time, distance },
         })
-        .collect::<Vec<Race>>();
+    }
 
-    races.iter().map(|r| r.num_winners()).product()
+    fn part_one(&self) -> Answer {
+        part_one(&self.races).into()
+    }
+
+    fn part_two(&self) -> Answer {
+        part_two(&self.race).into()
+    }
 }
 
-fn part_two(times_str: &str, distances_str: &str) -> usize {
-    let re = Regex::new(r"\d+").unwrap();
-    let time = re
-        .find_iter(times_str)
-        .map(|s| s.as_str())
-        .collect::<Vec<&str>>()
-        .join("")
-        .parse::<usize>()
-        .unwrap();
-    let distance = re
-        .find_iter(distances_str)
-        .map(|s| s.as_str())
-        .collect::<Vec<&str>>()
-        .join("")
-        .parse::<usize>()
-        .unwrap();
+fn part_one(races: &[Race]) -> usize {
+    races.iter().map(|r| r.num_winners()).product()
+}
 
-    Race { time, distance }.num_winners()
+fn part_two(race: &Race) -> usize {
+    race.num_winners()
 }
@@ -1,5 +1,6 @@
-use crate::utils;
-use regex::Regex;
+use crate::error::{Error, Result};
+use crate::solutions::{Answer, Solution};
+use crate::utils::{self, parse};
 
 struct Draw {
     num_blue: i32,
@@ -7,28 +8,36 @@ struct Draw {
     num_green: i32,
 }
 impl Draw {
-    fn parse_draw(draw: &str) -> Draw {
-        let re = Regex::new(r"(\d+)\s+(blue|red|green)").expect("Invalid regex");
-
+    /// Parses one `<n> <colour>, ...` draw of the `game` line.
+    fn parse_draw(game: &str, draw: &str) -> Result<Draw> {
         let mut num_blue = 0;
         let mut num_red = 0;
         let mut num_green = 0;
 
-        for cap in re.captures_iter(draw) {
-            let num = cap[1].parse::<i32>().unwrap();
-            let color = &cap[2];
-            match color {
+        for cubes in draw.split(',') {
+            let (num, color) = cubes
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| Error::at(game, cubes, "expected `<number> <colour>`"))?;
+            let num = utils::parse_number::<i32>(game, num)?;
+            match color.trim() {
                 "blue" => num_blue = num,
                 "red" => num_red = num,
                 "green" => num_green = num,
-                _ => (),
+                _ => {
+                    return Err(Error::at(
+                        game,
+                        color,
+                        format!("invalid colour `{}`", color),
+                    ))
+                }
             }
         }
-        Draw {
+        Ok(Draw {
             num_blue,
             num_red,
             num_green,
-        }
+        })
     }
 }
 
@@ -42,18 +51,14 @@ const MAX_NUM_GREEN: i32 = 13;
 const MAX_NUM_BLUE: i32 = 14;
 
 impl Game {
-    pub fn parse_game(game: &str) -> Game {
-        let first = game.split(':').collect::<Vec<&str>>();
-        let id = first[0].split(' ').collect::<Vec<&str>>()[1]
-            .parse::<i32>()
-            .unwrap();
-        let draws = first[1];
-        let draws = draws.split(';').collect::<Vec<&str>>();
+    pub fn parse_game(game: &str) -> Result<Game> {
+        let (header, draws) = parse::key_value(game, game, "Game <id>: <draws>")?;
+        let id = parse::labelled_number::<i32>(game, header, "Game")?;
         let draws = draws
-            .iter()
-            .map(|draw| Draw::parse_draw(draw))
-            .collect::<Vec<Draw>>();
-        Game { id, draws }
+            .split(';')
+            .map(|draw| Draw::parse_draw(game, draw))
+            .collect::<Result<Vec<Draw>>>()?;
+        Ok(Game { id, draws })
     }
 
     fn is_possible(&self) -> bool {
@@ -100,17 +105,22 @@ impl Games {
     }
 }
 
-pub fn run(test_mode: bool) {
-    let lines = utils::read_day_as_lines(2, test_mode);
-    let games = Games(
-        lines
-            .iter()
-            .map(|line| Game::parse_game(line))
-            .collect::<Vec<Game>>(),
-    );
+pub struct DayTwo {
+    games: Games,
+}
+impl Solution for DayTwo {
+    fn parse(input: &str) -> Result<Self> {
+        let games = Games(utils::parse_lines(input, Game::parse_game)?);
+        Ok(DayTwo { games })
+    }
 
-    println!("Part one: {}", part_one(&games));
-    println!("Part two: {}", part_two(&games));
+    fn part_one(&self) -> Answer {
+        part_one(&self.games).into()
+    }
+
+    fn part_two(&self) -> Answer {
+        part_two(&self.games).into()
+    }
 }
 
 fn part_one(games: &Games) -> i32 {
@@ -0,0 +1,567 @@
+use crate::aoc::{self, YEAR};
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::http::HttpClient;
+use crate::solutions::Part;
+use crate::utils;
+use crate::utils::parse::Pattern;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What adventofcode.com said about a submitted answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Correct,
+    Wrong,
+    TooHigh,
+    TooLow,
+    /// The answer was not checked because the last one was sent too recently.
+    RateLimited,
+    /// The part was already solved, so the answer was not checked.
+    AlreadySolved,
+    Unknown,
+}
+impl Outcome {
+    fn is_wrong(&self) -> bool {
+        matches!(self, Outcome::Wrong | Outcome::TooHigh | Outcome::TooLow)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Outcome::Correct => "correct",
+            Outcome::Wrong => "wrong",
+            Outcome::TooHigh => "too_high",
+            Outcome::TooLow => "too_low",
+            Outcome::RateLimited => "rate_limited",
+            Outcome::AlreadySolved => "already_solved",
+            Outcome::Unknown => "unknown",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Outcome> {
+        [
+            Outcome::Correct,
+            Outcome::Wrong,
+            Outcome::TooHigh,
+            Outcome::TooLow,
+            Outcome::RateLimited,
+            Outcome::AlreadySolved,
+            Outcome::Unknown,
+        ]
+        .into_iter()
+        .find(|o| o.name() == name)
+    }
+}
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Correct => write!(f, "That's the right answer!"),
+            Outcome::Wrong => write!(f, "That's not the right answer."),
+            Outcome::TooHigh => write!(f, "That's not the right answer: it's too high."),
+            Outcome::TooLow => write!(f, "That's not the right answer: it's too low."),
+            Outcome::RateLimited => write!(f, "An answer was sent too recently."),
+            Outcome::AlreadySolved => write!(f, "This part is already solved."),
+            Outcome::Unknown => write!(f, "The response could not be understood."),
+        }
+    }
+}
+
+/// Reads the outcome and how long to wait before the next attempt from the
+/// page adventofcode.com returns for a submitted answer.
+pub fn parse_response(body: &str) -> (Outcome, Duration) {
+    let outcome = if body.contains("That's the right answer") {
+        Outcome::Correct
+    } else if body.contains("You gave an answer too recently") {
+        Outcome::RateLimited
+    } else if body.contains("You don't seem to be solving the right level") {
+        Outcome::AlreadySolved
+    } else if body.contains("That's not the right answer") {
+        if body.contains("your answer is too high") {
+            Outcome::TooHigh
+        } else if body.contains("your answer is too low") {
+            Outcome::TooLow
+        } else {
+            Outcome::Wrong
+        }
+    } else {
+        Outcome::Unknown
+    };
+    (outcome, parse_wait(body))
+}
+
+static LEFT_TO_WAIT: Pattern = Pattern::new(r"(?:(\d+)m\s*)?(\d+)s left to wait");
+static WAIT_MINUTES: Pattern = Pattern::new(r"(?i)wait (one|\d+) minutes?");
+
+/// Finds "you have 1m 23s left to wait" or "please wait 5 minutes" in `body`.
+fn parse_wait(body: &str) -> Duration {
+    if let Some(caps) = LEFT_TO_WAIT.deref().captures(body) {
+        let minutes = caps
+            .get(1)
+            .map_or(0, |m| m.as_str().parse::<u64>().unwrap_or(0));
+        let seconds = caps[2].parse::<u64>().unwrap_or(0);
+        return Duration::from_secs(minutes * 60 + seconds);
+    }
+    if let Some(caps) = WAIT_MINUTES.deref().captures(body) {
+        let minutes = match &caps[1] {
+            m if m.eq_ignore_ascii_case("one") => 1,
+            m => m.parse::<u64>().unwrap_or(1),
+        };
+        return Duration::from_secs(minutes * 60);
+    }
+    Duration::ZERO
+}
+
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub day: u32,
+    pub part: Part,
+    pub outcome: Outcome,
+    pub wait: Duration,
+    pub answer: String,
+}
+
+/// Every answer sent so far, kept next to the real inputs since answers
+/// belong to an input.
+///
+/// The history file has one attempt per line:
+/// `<unix time> <day> <part> <outcome> <seconds to wait> <answer>`.
+#[derive(Debug, Default)]
+pub struct History {
+    pub attempts: Vec<Attempt>,
+}
+impl History {
+    pub fn path(config: &Config) -> PathBuf {
+        config.input_dir().join("submissions.txt")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<History> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(History::default());
+        }
+        let attempts = utils::parse_lines(&utils::read_file_as_string(path)?, |line| {
+            let fields = line.splitn(6, ' ').collect::<Vec<&str>>();
+            if fields.len() != 6 {
+                return Err(Error::at(
+                    line,
+                    line,
+                    "expected `<time> <day> <part> <outcome> <wait> <answer>`",
+                ));
+            }
+            Ok(Attempt {
+                time: utils::parse_number(line, fields[0])?,
+                day: utils::parse_number(line, fields[1])?,
+                part: match fields[2] {
+                    "1" => Part::One,
+                    "2" => Part::Two,
+                    p => return Err(Error::at(line, p, "expected part `1` or `2`")),
+                },
+                outcome: Outcome::from_name(fields[3])
+                    .ok_or_else(|| Error::at(line, fields[3], "unknown outcome"))?,
+                wait: Duration::from_secs(utils::parse_number(line, fields[4])?),
+                answer: fields[5].to_string(),
+            })
+        })?;
+        Ok(History { attempts })
+    }
+
+    pub fn append(path: impl AsRef<Path>, attempt: &Attempt) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| Error::io(path, e))?;
+        writeln!(
+            file,
+            "{} {} {} {} {} {}",
+            attempt.time,
+            attempt.day,
+            attempt.part.number(),
+            attempt.outcome.name(),
+            attempt.wait.as_secs(),
+            attempt.answer
+        )
+        .map_err(|e| Error::io(path, e))
+    }
+
+    /// Explains why `answer` should not be sent for `part` of `day` at `now`,
+    /// if there is a reason not to.
+    pub fn refusal(&self, day: u32, part: Part, answer: &str, now: u64) -> Option<String> {
+        if let Some(last) = self.attempts.last() {
+            let ready = last.time + last.wait.as_secs();
+            if ready > now {
+                return Some(format!(
+                    "the last answer was sent too recently, wait {}s before trying again",
+                    ready - now
+                ));
+            }
+        }
+
+        let attempts = self
+            .attempts
+            .iter()
+            .filter(|a| a.day == day && a.part == part);
+        let number = answer.parse::<i64>().ok();
+        for attempt in attempts {
+            if attempt.outcome == Outcome::Correct {
+                return Some(format!("already solved with {}", attempt.answer));
+            }
+            if attempt.outcome.is_wrong() && attempt.answer == answer {
+                return Some(format!("{} was already rejected", answer));
+            }
+            let bound = attempt.answer.parse::<i64>().ok();
+            match (attempt.outcome, number, bound) {
+                (Outcome::TooHigh, Some(n), Some(high)) if n >= high => {
+                    return Some(format!("{} was too high, so {} is too", high, n));
+                }
+                (Outcome::TooLow, Some(n), Some(low)) if n <= low => {
+                    return Some(format!("{} was too low, so {} is too", low, n));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Sends `answer` for `part` of `day`, unless the submission history shows it
+/// would be wasted, and records the attempt.
+pub fn submit(
+    client: &dyn HttpClient,
+    config: &Config,
+    day: u32,
+    part: Part,
+    answer: &str,
+) -> Result<Attempt> {
+    if answer.is_empty() || answer.contains(char::is_whitespace) {
+        return Err(Error::Refused(format!("cannot submit `{}`", answer)));
+    }
+    let path = History::path(config);
+    let history = History::load(&path)?;
+    if let Some(reason) = history.refusal(day, part, answer, now()) {
+        return Err(Error::Refused(reason));
+    }
+
+    let url = format!("{}/{}/day/{}/answer", config.base_url, YEAR, day);
+    let cookie = format!("session={}", aoc::session(config)?);
+    let level = part.number().to_string();
+    let response = client.post_form(
+        &url,
+        &[("Cookie", &cookie)],
+        &[("level", &level), ("answer", answer)],
+    )?;
+    if response.status != 200 {
+        return Err(Error::Http(format!(
+            "POST {}: status {}",
+            url, response.status
+        )));
+    }
+
+    let (outcome, wait) = parse_response(&response.body);
+    let attempt = Attempt {
+        time: now(),
+        day,
+        part,
+        outcome,
+        wait,
+        answer: answer.to_string(),
+    };
+    History::append(&path, &attempt)?;
+    Ok(attempt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::fake::{FakeClient, LocalServer};
+    use crate::http::UreqClient;
+    use crate::utils::TempDir;
+
+    const CORRECT: &str = "<article><p>That's the right answer! You are one gold star closer to restoring snow operations.</p></article>";
+    const TOO_HIGH: &str = "<article><p>That's not the right answer; your answer is too high. If you're stuck, make sure you're using the full input data. Please wait one minute before trying again.</p></article>";
+    const TOO_LOW: &str = "<article><p>That's not the right answer; your answer is too low. Because you have guessed incorrectly 5 times on this puzzle, please wait 5 minutes before trying again.</p></article>";
+    const TOO_RECENTLY: &str = "<article><p>You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 1m 23s left to wait.</p></article>";
+
+    fn config_in(dir: &TempDir) -> Config {
+        Config {
+            data_dir: dir.path().to_path_buf(),
+            session: Some("cookie".to_string()),
+            base_url: "http://aoc.test".to_string(),
+            ..Config::default()
+        }
+    }
+
+    fn attempt(day: u32, part: Part, outcome: Outcome, answer: &str) -> Attempt {
+        Attempt {
+            time: 0,
+            day,
+            part,
+            outcome,
+            wait: Duration::ZERO,
+            answer: answer.to_string(),
+        }
+    }
+
+    /// A too high answer of 900 whose wait ran out long ago.
+    fn high_attempt() -> Attempt {
+        Attempt {
+            wait: Duration::from_secs(60),
+            ..attempt(5, Part::One, Outcome::TooHigh, "900")
+        }
+    }
+
+    #[test]
+    fn parses_outcomes() {
+        assert_eq!(parse_response(CORRECT), (Outcome::Correct, Duration::ZERO));
+        assert_eq!(
+            parse_response(TOO_HIGH),
+            (Outcome::TooHigh, Duration::from_secs(60))
+        );
+        assert_eq!(
+            parse_response(TOO_LOW),
+            (Outcome::TooLow, Duration::from_secs(300))
+        );
+        assert_eq!(
+            parse_response(TOO_RECENTLY),
+            (Outcome::RateLimited, Duration::from_secs(83))
+        );
+        assert_eq!(
+            parse_response("You don't seem to be solving the right level.").0,
+            Outcome::AlreadySolved
+        );
+        assert_eq!(
+            parse_response("That's not the right answer.").0,
+            Outcome::Wrong
+        );
+        assert_eq!(parse_response("<html></html>").0, Outcome::Unknown);
+    }
+
+    #[test]
+    fn parses_waits() {
+        assert_eq!(
+            parse_wait("You have 45s left to wait."),
+            Duration::from_secs(45)
+        );
+        assert_eq!(
+            parse_wait("You have 2m 5s left to wait."),
+            Duration::from_secs(125)
+        );
+        assert_eq!(
+            parse_wait("Please wait one minute."),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            parse_wait("please Wait 10 minutes"),
+            Duration::from_secs(600)
+        );
+        assert_eq!(parse_wait("Nothing to see here."), Duration::ZERO);
+    }
+
+    #[test]
+    fn submits_and_records_a_correct_answer() {
+        let dir = TempDir::new("submit");
+        let config = config_in(&dir);
+        let client = FakeClient::new([(200, CORRECT)]);
+        let attempt = submit(&client, &config, 3, Part::Two, "4361").unwrap();
+        assert_eq!(attempt.outcome, Outcome::Correct);
+
+        let requests = client.requests.borrow();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].url, "http://aoc.test/2023/day/3/answer");
+        assert_eq!(
+            requests[0].headers,
+            [("Cookie".to_string(), "session=cookie".to_string())]
+        );
+        assert_eq!(
+            requests[0].form,
+            [
+                ("level".to_string(), "2".to_string()),
+                ("answer".to_string(), "4361".to_string())
+            ]
+        );
+
+        let history = History::load(History::path(&config)).unwrap();
+        assert_eq!(history.attempts.len(), 1);
+        assert_eq!(history.attempts[0].answer, "4361");
+        assert_eq!(history.attempts[0].outcome, Outcome::Correct);
+        // Once solved, nothing more is sent for that part.
+        let again = submit(&client, &config, 3, Part::Two, "4362");
+        assert!(matches!(again, Err(Error::Refused(_))));
+        assert_eq!(client.requests.borrow().len(), 1);
+    }
+
+    #[test]
+    fn records_too_high_and_too_low_with_their_waits() {
+        let dir = TempDir::new("submit");
+        let config = config_in(&dir);
+        let client = FakeClient::new([(200, TOO_HIGH), (200, TOO_LOW)]);
+        let high = submit(&client, &config, 5, Part::One, "900").unwrap();
+        assert_eq!(
+            (high.outcome, high.wait),
+            (Outcome::TooHigh, Duration::from_secs(60))
+        );
+
+        // The wait after the last answer has to run out first.
+        let early = submit(&client, &config, 5, Part::One, "500");
+        assert!(matches!(early, Err(Error::Refused(reason)) if reason.contains("too recently")));
+        assert_eq!(client.requests.borrow().len(), 1);
+
+        // Once it has, the answer goes through.
+        let dir = TempDir::new("submit");
+        let config = config_in(&dir);
+        History::append(History::path(&config), &high_attempt()).unwrap();
+        let low = submit(&client, &config, 5, Part::One, "500").unwrap();
+        assert_eq!(
+            (low.outcome, low.wait),
+            (Outcome::TooLow, Duration::from_secs(300))
+        );
+        let history = History::load(History::path(&config)).unwrap();
+        let outcomes = history.attempts.iter().map(|a| a.outcome);
+        assert_eq!(
+            outcomes.collect::<Vec<_>>(),
+            [Outcome::TooHigh, Outcome::TooLow]
+        );
+    }
+
+    #[test]
+    fn records_being_rate_limited() {
+        let dir = TempDir::new("submit");
+        let config = config_in(&dir);
+        let client = FakeClient::new([(200, TOO_RECENTLY)]);
+        let attempt = submit(&client, &config, 1, Part::One, "142").unwrap();
+        assert_eq!(attempt.outcome, Outcome::RateLimited);
+        assert_eq!(attempt.wait, Duration::from_secs(83));
+
+        let history = History::load(History::path(&config)).unwrap();
+        let reason = history.refusal(1, Part::Two, "1", attempt.time + 10);
+        assert_eq!(
+            reason.as_deref(),
+            Some("the last answer was sent too recently, wait 73s before trying again")
+        );
+        assert_eq!(history.refusal(1, Part::One, "1", attempt.time + 83), None);
+    }
+
+    #[test]
+    fn refuses_without_sending() {
+        let dir = TempDir::new("submit");
+        let config = config_in(&dir);
+        History::append(
+            History::path(&config),
+            &attempt(2, Part::One, Outcome::Wrong, "8"),
+        )
+        .unwrap();
+        let client = FakeClient::new([]);
+        let refused = submit(&client, &config, 2, Part::One, "8");
+        assert!(
+            matches!(refused, Err(Error::Refused(reason)) if reason == "8 was already rejected")
+        );
+        for answer in ["", "1 2"] {
+            let refused = submit(&client, &config, 2, Part::One, answer);
+            assert!(matches!(refused, Err(Error::Refused(_))));
+        }
+        assert!(client.requests.borrow().is_empty());
+    }
+
+    #[test]
+    fn refuses_known_wrong_and_out_of_bounds_answers() {
+        let history = History {
+            attempts: Vec::from([
+                attempt(7, Part::One, Outcome::Wrong, "250"),
+                attempt(7, Part::One, Outcome::TooHigh, "500"),
+                attempt(7, Part::One, Outcome::TooLow, "100"),
+                attempt(7, Part::Two, Outcome::Correct, "42"),
+            ]),
+        };
+        let refusal = |part, answer| history.refusal(7, part, answer, 1000);
+        assert_eq!(
+            refusal(Part::One, "250").as_deref(),
+            Some("250 was already rejected")
+        );
+        assert_eq!(
+            refusal(Part::One, "500").as_deref(),
+            Some("500 was already rejected")
+        );
+        assert_eq!(
+            refusal(Part::One, "600").as_deref(),
+            Some("500 was too high, so 600 is too")
+        );
+        assert_eq!(
+            refusal(Part::One, "50").as_deref(),
+            Some("100 was too low, so 50 is too")
+        );
+        assert_eq!(refusal(Part::One, "300"), None);
+        assert_eq!(refusal(Part::One, "abc"), None);
+        assert_eq!(
+            refusal(Part::Two, "43").as_deref(),
+            Some("already solved with 42")
+        );
+        // Other days are not affected.
+        assert_eq!(history.refusal(8, Part::One, "600", 1000), None);
+    }
+
+    #[test]
+    fn reports_error_statuses() {
+        let dir = TempDir::new("submit");
+        let config = config_in(&dir);
+        let client = FakeClient::new([(500, "oops")]);
+        let failed = submit(&client, &config, 4, Part::One, "13");
+        assert!(matches!(failed, Err(Error::Http(_))));
+        assert!(History::load(History::path(&config))
+            .unwrap()
+            .attempts
+            .is_empty());
+    }
+
+    #[test]
+    fn submits_over_http() {
+        let dir = TempDir::new("submit");
+        let server = LocalServer::start([(200, CORRECT)]);
+        let config = Config {
+            base_url: server.url.clone(),
+            ..config_in(&dir)
+        };
+        let submitted = submit(&UreqClient::default(), &config, 5, Part::Two, "46").unwrap();
+        assert_eq!(submitted.outcome, Outcome::Correct);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert!(
+            request.starts_with("POST /2023/day/5/answer HTTP/1.1\r\n"),
+            "{}",
+            request
+        );
+        assert!(
+            request.contains("\r\nCookie: session=cookie\r\n"),
+            "{}",
+            request
+        );
+        assert!(
+            request.contains("\r\nContent-Type: application/x-www-form-urlencoded\r\n"),
+            "{}",
+            request
+        );
+        assert!(
+            request.ends_with("\r\n\r\nlevel=2&answer=46"),
+            "{}",
+            request
+        );
+    }
+}
@@ -1,36 +1,193 @@
+pub mod grid;
+pub mod parse;
+pub mod ranges;
+
+use crate::error::{Error, Result};
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
 
-pub fn read_day_as_lines(day: u32, is_test: bool) -> Vec<String> {
-    let filename = match is_test {
-        true => format!("./data/day{:1}_test.data", day),
-        false => format!("./data/day{:1}.data", day),
-    };
-    read_file_as_lines(&filename)
+/// The default example, `dayN_test.data`.
+pub const DEFAULT_EXAMPLE: &str = "test";
+
+/// A day's puzzle input: either the real input, `dayN.data`, or one of the
+/// named examples, `dayN_<name>.data`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Dataset {
+    Real,
+    Example(String),
 }
-pub fn read_day_as_string(day: u32, is_test: bool) -> String {
-    let filename = match is_test {
-        true => format!("./data/day{:1}_test.data", day),
-        false => format!("./data/day{:1}.data", day),
-    };
-    read_file_as_string(&filename)
+impl Dataset {
+    pub fn default_example() -> Dataset {
+        Dataset::Example(DEFAULT_EXAMPLE.to_string())
+    }
+
+    pub fn file_name(&self, day: u32) -> String {
+        match self {
+            Dataset::Real => format!("day{:1}.data", day),
+            Dataset::Example(name) => format!("day{:1}_{}.data", day, name),
+        }
+    }
+}
+impl FromStr for Dataset {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "real" => Ok(Dataset::Real),
+            _ if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                Ok(Dataset::Example(s.to_string()))
+            }
+            _ => Err(format!(
+                "invalid dataset `{}`: expected `real` or an example name made of letters, digits and `_`",
+                s
+            )),
+        }
+    }
+}
+impl fmt::Display for Dataset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dataset::Real => f.pad("real"),
+            Dataset::Example(name) => f.pad(name),
+        }
+    }
 }
 
-fn read_file_as_lines(filename: &str) -> Vec<String> {
-    let mut lines = Vec::new();
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        lines.push(line.unwrap());
+/// Whether `name` is `dayN.data` or one of day N's examples, `dayN_<name>.data`.
+pub fn is_day_file(name: &str, day: u32) -> bool {
+    let Some(stem) = name.strip_suffix(".data") else {
+        return false;
+    };
+    match stem.strip_prefix(&format!("day{}", day)) {
+        Some(rest) => rest.is_empty() || rest.starts_with('_'),
+        None => false,
     }
-    lines
 }
 
-fn read_file_as_string(filename: &str) -> String {
+pub fn split_lines(input: &str) -> Vec<String> {
+    input.lines().map(|line| line.to_string()).collect()
+}
+
+pub fn read_file_as_string(filename: impl AsRef<Path>) -> Result<String> {
+    let filename = filename.as_ref();
     let mut contents = String::new();
-    let file = File::open(filename).unwrap();
+    let file = File::open(filename).map_err(|e| Error::io(filename, e))?;
     let mut reader = BufReader::new(file);
-    let _ = reader.read_to_string(&mut contents);
+    reader
+        .read_to_string(&mut contents)
+        .map_err(|e| Error::io(filename, e))?;
+
+    Ok(contents)
+}
 
-    contents
+pub fn read_stdin_as_string() -> Result<String> {
+    let mut contents = String::new();
+    io::stdin()
+        .read_to_string(&mut contents)
+        .map_err(|e| Error::io("<stdin>", e))?;
+    Ok(contents)
+}
+
+/// Whether input is piped or redirected into the program, as in
+/// `cat input | advent2023 --day 7` or `advent2023 --day 7 < input`. A terminal
+/// or `/dev/null` on stdin doesn't count.
+pub fn stdin_is_piped() -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        std::fs::metadata("/dev/stdin").is_ok_and(|m| m.file_type().is_fifo() || m.is_file())
+    }
+    #[cfg(not(unix))]
+    {
+        use std::io::IsTerminal;
+        !io::stdin().is_terminal()
+    }
+}
+
+/// Returns the 1-based line and column at which `token`, a slice of `text`,
+/// starts. Falls back to the start of `text` if `token` is not a slice of it.
+pub fn position_of(text: &str, token: &str) -> (usize, usize) {
+    let offset = (token.as_ptr() as usize).wrapping_sub(text.as_ptr() as usize);
+    if offset > text.len() {
+        return (1, 1);
+    }
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Parses `token`, a slice of `text`, reporting its position on failure.
+pub fn parse_number<T: FromStr>(text: &str, token: &str) -> Result<T> {
+    token
+        .parse::<T>()
+        .map_err(|_| Error::at(text, token, format!("invalid number `{}`", token)))
+}
+
+/// Parses each line of `input` with `parse`, numbering errors by line.
+pub fn parse_lines<T>(input: &str, parse: impl Fn(&str) -> Result<T>) -> Result<Vec<T>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse(line).map_err(|e| e.offset_lines(i)))
+        .collect()
+}
+
+fn parse_day(s: &str) -> std::result::Result<u32, String> {
+    match s.trim().parse::<u32>() {
+        Ok(day) if (1..=25).contains(&day) => Ok(day),
+        _ => Err(format!("Invalid day: {}", s)),
+    }
+}
+
+/// Parses a comma separated list of days and inclusive ranges, e.g. `1,3-5`.
+pub fn parse_days(s: &str) -> std::result::Result<Vec<u32>, String> {
+    let mut days = Vec::new();
+    for item in s.split(',') {
+        match item.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse_day(start)?, parse_day(end)?);
+                if start > end {
+                    return Err(format!("Invalid day range: {}", item));
+                }
+                days.extend(start..=end);
+            }
+            None => days.push(parse_day(item)?),
+        }
+    }
+    Ok(days)
+}
+
+/// A fresh directory under the system's temporary directory, removed with
+/// everything in it when dropped. For tests.
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+#[cfg(test)]
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "advent2023-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).expect("could not create a temporary directory");
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
 }
@@ -0,0 +1,620 @@
+//! Rectangular grids of cells, as drawn by many puzzle inputs.
+
+use crate::error::{Error, Result};
+use crate::utils;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// A cell's row and column, counting from the top left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+impl Pos {
+    pub const fn new(row: usize, col: usize) -> Pos {
+        Pos { row, col }
+    }
+}
+
+/// A step from a cell to one of its eight neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+impl Direction {
+    /// Up, right, down and left, clockwise.
+    pub const CARDINAL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+    /// Every direction, clockwise from up.
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::UpRight,
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+    ];
+
+    /// The change in row and column for one step.
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::UpRight => (-1, 1),
+            Direction::Right => (0, 1),
+            Direction::DownRight => (1, 1),
+            Direction::Down => (1, 0),
+            Direction::DownLeft => (1, -1),
+            Direction::Left => (0, -1),
+            Direction::UpLeft => (-1, -1),
+        }
+    }
+
+    /// The direction `eighths` eighths of a turn clockwise from this one.
+    fn turn(self, eighths: usize) -> Direction {
+        let index = Direction::ALL.iter().position(|&d| d == self).unwrap_or(0);
+        Direction::ALL[(index + eighths) % 8]
+    }
+
+    /// A quarter turn clockwise.
+    pub fn turn_right(self) -> Direction {
+        self.turn(2)
+    }
+
+    /// A quarter turn anticlockwise.
+    pub fn turn_left(self) -> Direction {
+        self.turn(6)
+    }
+
+    pub fn opposite(self) -> Direction {
+        self.turn(4)
+    }
+}
+
+/// A rectangular grid, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+impl<T> Grid<T> {
+    /// Builds a grid from its rows, or returns `None` if they differ in length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Grid<T>> {
+        let width = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        Some(Grid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Parses a grid with one line of `text` per row and one character per
+    /// cell. `cell` turns each character into a cell, or returns `None` if it
+    /// isn't a valid one.
+    pub fn parse(text: &str, mut cell: impl FnMut(char) -> Option<T>) -> Result<Grid<T>> {
+        let mut rows = Vec::new();
+        for line in text.lines() {
+            let row = line
+                .char_indices()
+                .map(|(i, c)| {
+                    cell(c)
+                        .ok_or_else(|| Error::at(text, &line[i..], format!("invalid cell `{}`", c)))
+                })
+                .collect::<Result<Vec<T>>>()?;
+            if let Some(first) = rows.first().map(|first: &Vec<T>| first.len()) {
+                if row.len() != first {
+                    let (line, _) = utils::position_of(text, line);
+                    return Err(Error::parse(
+                        line,
+                        row.len().min(first) + 1,
+                        format!("expected {} columns, found {}", first, row.len()),
+                    ));
+                }
+            }
+            rows.push(row);
+        }
+        Ok(Grid::from_rows(rows).expect("rows have the same length"))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        match pos.row < self.height && pos.col < self.width {
+            true => Some(&self.cells[pos.row * self.width + pos.col]),
+            false => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        match pos.row < self.height && pos.col < self.width {
+            true => Some(&mut self.cells[pos.row * self.width + pos.col]),
+            false => None,
+        }
+    }
+
+    /// The cell one step from `pos` in `direction`, if that is on the grid.
+    pub fn step(&self, pos: Pos, direction: Direction) -> Option<Pos> {
+        let (d_row, d_col) = direction.delta();
+        let row = pos.row.checked_add_signed(d_row)?;
+        let col = pos.col.checked_add_signed(d_col)?;
+        match row < self.height && col < self.width {
+            true => Some(Pos { row, col }),
+            false => None,
+        }
+    }
+
+    /// The cells above, right of, below and left of `pos` that are on the grid.
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Direction::CARDINAL
+            .into_iter()
+            .filter_map(move |d| self.step(pos, d))
+    }
+
+    /// The cells around `pos`, diagonals included, that are on the grid.
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |d| self.step(pos, d))
+    }
+
+    /// The cells from `pos`, which is included, to the edge of the grid in
+    /// `direction`.
+    pub fn ray(&self, pos: Pos, direction: Direction) -> impl Iterator<Item = Pos> + '_ {
+        let start = self.get(pos).map(|_| pos);
+        std::iter::successors(start, move |&pos| self.step(pos, direction))
+    }
+
+    /// Every position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| Pos::new(i / width, i % width))
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// The cells in row `row`. Panics if there is no such row.
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on 0, and a grid without columns has no cells anyway.
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// The cells in column `col`, top to bottom, or none if there is no such column.
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        let height = match col < self.width {
+            true => self.height,
+            false => 0,
+        };
+        self.cells
+            .iter()
+            .skip(col)
+            .step_by(self.width.max(1))
+            .take(height)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |col| self.column(col))
+    }
+
+    /// The diagonals running down and to the right, starting from the bottom
+    /// left corner.
+    pub fn diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        let starts = (0..self.height)
+            .rev()
+            .map(|row| Pos::new(row, 0))
+            .chain((1..self.width).map(|col| Pos::new(0, col)));
+        starts.map(move |start| {
+            self.ray(start, Direction::DownRight)
+                .map(move |pos| &self[pos])
+        })
+    }
+
+    /// The diagonals running down and to the left, starting from the top left
+    /// corner.
+    pub fn anti_diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        let starts = (0..self.width)
+            .map(|col| Pos::new(0, col))
+            .chain((1..self.height).map(|row| Pos::new(row, self.width.saturating_sub(1))));
+        starts.map(move |start| {
+            self.ray(start, Direction::DownLeft)
+                .map(move |pos| &self[pos])
+        })
+    }
+
+    /// Every run of neighbouring cells along a row for which `in_span` is
+    /// true, such as the digits of a number or the letters of a word, with
+    /// `value` worked out from its cells, and an index of which span each
+    /// cell is in.
+    pub fn spans<V>(
+        &self,
+        mut in_span: impl FnMut(&T) -> bool,
+        mut value: impl FnMut(&[T]) -> V,
+    ) -> Spans<V> {
+        let mut spans = Vec::new();
+        let mut index = Grid::new(self.width, self.height, None);
+        for (row, cells) in self.rows().enumerate() {
+            let mut col = 0;
+            while col < cells.len() {
+                if !in_span(&cells[col]) {
+                    col += 1;
+                    continue;
+                }
+                let start = col;
+                while col < cells.len() && in_span(&cells[col]) {
+                    index[Pos::new(row, col)] = Some(spans.len());
+                    col += 1;
+                }
+                spans.push(Span {
+                    start: Pos::new(row, start),
+                    end: Pos::new(row, col - 1),
+                    value: value(&cells[start..col]),
+                });
+            }
+        }
+        Spans { spans, index }
+    }
+
+    /// The cells around `span`, diagonals included, that are on the grid.
+    pub fn around_span<'a, V>(&'a self, span: &'a Span<V>) -> impl Iterator<Item = Pos> + 'a {
+        let rows = span.start.row.saturating_sub(1)..=span.end.row + 1;
+        rows.flat_map(move |row| {
+            let cols = span.start.col.saturating_sub(1)..=span.end.col + 1;
+            cols.map(move |col| Pos::new(row, col))
+        })
+        .filter(move |&pos| self.get(pos).is_some() && !span.contains(pos))
+    }
+
+    /// A grid of the same shape with `f` applied to every cell.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// The grid flipped over its main diagonal, so rows become columns.
+    pub fn transpose(&self) -> Grid<T> {
+        self.rebuild(self.height, self.width, |pos| Pos::new(pos.col, pos.row))
+    }
+
+    /// The grid turned a quarter turn clockwise.
+    pub fn rotate_right(&self) -> Grid<T> {
+        let height = self.height;
+        self.rebuild(height, self.width, |pos| {
+            Pos::new(height - 1 - pos.col, pos.row)
+        })
+    }
+
+    /// The grid turned a quarter turn anticlockwise.
+    pub fn rotate_left(&self) -> Grid<T> {
+        let width = self.width;
+        self.rebuild(self.height, width, |pos| {
+            Pos::new(pos.col, width - 1 - pos.row)
+        })
+    }
+
+    /// A `width` by `height` grid whose cell at each position is this grid's
+    /// cell at `source(position)`.
+    fn rebuild(&self, width: usize, height: usize, source: impl Fn(Pos) -> Pos) -> Grid<T> {
+        let cells = (0..width * height)
+            .map(|i| self[source(Pos::new(i / width, i % width))].clone())
+            .collect();
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+}
+/// A run of cells along a row, from `start` to `end` inclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span<V> {
+    pub start: Pos,
+    pub end: Pos,
+    pub value: V,
+}
+impl<V> Span<V> {
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.row == self.start.row && (self.start.col..=self.end.col).contains(&pos.col)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let row = self.start.row;
+        (self.start.col..=self.end.col).map(move |col| Pos::new(row, col))
+    }
+}
+
+/// The spans found in a grid, numbered in reading order, with the number of
+/// the span each cell is in. Two spans with the same value are still told
+/// apart by their numbers.
+#[derive(Debug, Clone)]
+pub struct Spans<V> {
+    spans: Vec<Span<V>>,
+    index: Grid<Option<usize>>,
+}
+impl<V> Spans<V> {
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Span<V>> {
+        self.spans.iter()
+    }
+
+    /// The number of the span that `pos` is in, if any.
+    pub fn id_at(&self, pos: Pos) -> Option<usize> {
+        self.index.get(pos).copied().flatten()
+    }
+
+    /// The span that `pos` is in, if any.
+    pub fn at(&self, pos: Pos) -> Option<&Span<V>> {
+        self.id_at(pos).map(|id| &self.spans[id])
+    }
+
+    /// The numbers of the spans next to `pos`, diagonals included, each once
+    /// and in reading order.
+    pub fn around(&self, pos: Pos) -> Vec<usize> {
+        let mut ids = self
+            .index
+            .neighbours8(pos)
+            .filter_map(|pos| self.id_at(pos))
+            .collect::<Vec<usize>>();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+impl<V> Index<usize> for Spans<V> {
+    type Output = Span<V>;
+
+    fn index(&self, id: usize) -> &Span<V> {
+        &self.spans[id]
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos).unwrap_or_else(|| {
+            panic!(
+                "{:?} is outside the {}x{} grid",
+                pos, self.width, self.height
+            )
+        })
+    }
+}
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the {}x{} grid", pos, width, height))
+    }
+}
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two rows of three cells.
+    fn small() -> Grid<char> {
+        Grid::parse("abc\ndef", Some).unwrap()
+    }
+
+    fn strings<'a>(lines: impl Iterator<Item = impl Iterator<Item = &'a char>>) -> Vec<String> {
+        lines.map(|line| line.collect()).collect()
+    }
+
+    #[test]
+    fn parse_and_shape() {
+        let grid = small();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[Pos::new(1, 2)], 'f');
+        assert_eq!(grid.get(Pos::new(2, 0)), None);
+        assert_eq!(grid.get(Pos::new(0, 3)), None);
+        assert_eq!(grid.row(1), ['d', 'e', 'f']);
+        assert_eq!(strings(grid.columns()), ["ad", "be", "cf"]);
+        assert_eq!(grid.column(3).count(), 0);
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+
+        let error = Grid::parse("abc\nde", Some).unwrap_err();
+        assert!(error.to_string().contains("expected 3 columns, found 2"));
+        assert!(Grid::parse("ab\nc?", |c| c.is_alphabetic().then_some(c)).is_err());
+        assert_eq!(
+            Grid::from_rows(Vec::from([Vec::from([1]), Vec::new()])),
+            None
+        );
+    }
+
+    #[test]
+    fn transpose_and_rotate() {
+        let grid = small();
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.rotate_right().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate_left().to_string(), "cf\nbe\nad\n");
+        assert_eq!(grid.transpose().transpose(), grid);
+        assert_eq!(grid.rotate_right().rotate_left(), grid);
+        assert_eq!(
+            grid.rotate_right().rotate_right(),
+            grid.rotate_left().rotate_left()
+        );
+        let turned = (0..4).fold(grid.clone(), |grid, _| grid.rotate_right());
+        assert_eq!(turned, grid);
+    }
+
+    #[test]
+    fn diagonals() {
+        let grid = small();
+        assert_eq!(strings(grid.diagonals()), ["d", "ae", "bf", "c"]);
+        assert_eq!(strings(grid.anti_diagonals()), ["a", "bd", "ce", "f"]);
+        let tall = grid.transpose();
+        assert_eq!(strings(tall.diagonals()), ["c", "bf", "ae", "d"]);
+        assert_eq!(strings(tall.anti_diagonals()), ["a", "db", "ec", "f"]);
+    }
+
+    #[test]
+    fn steps_stop_at_the_edges() {
+        let grid = small();
+        let corner = Pos::new(0, 0);
+        for direction in [
+            Direction::Up,
+            Direction::Left,
+            Direction::UpLeft,
+            Direction::DownLeft,
+        ] {
+            assert_eq!(grid.step(corner, direction), None, "{:?}", direction);
+        }
+        assert_eq!(grid.step(corner, Direction::Right), Some(Pos::new(0, 1)));
+        assert_eq!(
+            grid.step(corner, Direction::DownRight),
+            Some(Pos::new(1, 1))
+        );
+
+        let corner = Pos::new(1, 2);
+        for direction in [
+            Direction::Down,
+            Direction::Right,
+            Direction::DownRight,
+            Direction::UpRight,
+        ] {
+            assert_eq!(grid.step(corner, direction), None, "{:?}", direction);
+        }
+        assert_eq!(grid.step(corner, Direction::UpLeft), Some(Pos::new(0, 1)));
+        assert_eq!(grid.step(Pos::new(5, 5), Direction::UpLeft), None);
+
+        assert_eq!(grid.neighbours4(corner).count(), 2);
+        assert_eq!(grid.neighbours8(corner).count(), 3);
+        assert_eq!(grid.neighbours8(Pos::new(0, 1)).count(), 5);
+        let ray = grid.ray(Pos::new(1, 0), Direction::UpRight);
+        assert_eq!(ray.collect::<Vec<Pos>>(), [Pos::new(1, 0), Pos::new(0, 1)]);
+        assert_eq!(grid.ray(Pos::new(2, 0), Direction::Up).count(), 0);
+    }
+
+    #[test]
+    fn spans_along_rows() {
+        let grid = Grid::parse("12.3\n.*..\n45.5", Some).unwrap();
+        let spans = grid.spans(
+            |c| c.is_ascii_digit(),
+            |cells| cells.iter().collect::<String>(),
+        );
+        let found = spans
+            .iter()
+            .map(|span| (span.start, span.end, span.value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (Pos::new(0, 0), Pos::new(0, 1), "12"),
+                (Pos::new(0, 3), Pos::new(0, 3), "3"),
+                (Pos::new(2, 0), Pos::new(2, 1), "45"),
+                (Pos::new(2, 3), Pos::new(2, 3), "5"),
+            ]
+        );
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans.id_at(Pos::new(0, 1)), Some(0));
+        assert_eq!(spans.id_at(Pos::new(0, 2)), None);
+        assert_eq!(spans.id_at(Pos::new(9, 9)), None);
+        assert_eq!(
+            spans.at(Pos::new(2, 1)).map(|s| s.value.as_str()),
+            Some("45")
+        );
+        assert!(spans[0].contains(Pos::new(0, 1)));
+        assert!(!spans[0].contains(Pos::new(1, 1)));
+        assert_eq!(
+            spans[2].positions().collect::<Vec<Pos>>(),
+            [Pos::new(2, 0), Pos::new(2, 1)]
+        );
+
+        // A span ending the row ends at the last column.
+        let digits = Grid::parse("a12\n345", Some).unwrap();
+        let spans = digits.spans(|c| c.is_ascii_digit(), |cells| cells.len());
+        assert_eq!(spans.iter().map(|s| s.value).collect::<Vec<_>>(), [2, 3]);
+        let none = digits.spans(|c| *c == '?', |cells| cells.len());
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn around_spans_and_cells() {
+        let grid = Grid::parse("12.3\n.*..\n45.5", Some).unwrap();
+        let spans = grid.spans(|c| c.is_ascii_digit(), |cells| cells.len());
+        // Every span touches the middle, and each is listed once.
+        assert_eq!(spans.around(Pos::new(1, 1)), [0, 2]);
+        assert_eq!(spans.around(Pos::new(1, 2)), [0, 1, 2, 3]);
+        assert_eq!(spans.around(Pos::new(0, 0)), [0]);
+        assert_eq!(spans.around(Pos::new(1, 3)), [1, 3]);
+
+        let around = |id: usize| grid.around_span(&spans[id]).collect::<Vec<Pos>>();
+        assert_eq!(
+            around(0),
+            [
+                Pos::new(0, 2),
+                Pos::new(1, 0),
+                Pos::new(1, 1),
+                Pos::new(1, 2)
+            ]
+        );
+        assert_eq!(around(3), [Pos::new(1, 2), Pos::new(1, 3), Pos::new(2, 2)]);
+    }
+
+    #[test]
+    fn directions_turn() {
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::UpLeft.turn_right(), Direction::UpRight);
+        assert_eq!(Direction::DownRight.opposite(), Direction::UpLeft);
+        for direction in Direction::ALL {
+            assert_eq!(direction.turn_right().turn_left(), direction);
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Building blocks for puzzle input parsers.
+//!
+//! Every function takes the `text` being parsed along with the piece of it to
+//! look at, which must be a slice of `text`, so that errors point at the line
+//! and column where the input went wrong.
+
+use crate::error::{Error, Result};
+use crate::utils;
+use regex::{Captures, Regex};
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// A regex compiled the first time it is used, so that a parser can keep its
+/// patterns in `static`s instead of compiling them on every call.
+pub struct Pattern {
+    source: &'static str,
+    regex: OnceLock<Regex>,
+}
+impl Pattern {
+    pub const fn new(source: &'static str) -> Pattern {
+        Pattern {
+            source,
+            regex: OnceLock::new(),
+        }
+    }
+
+    /// The captures of the first match in `line`, or an error at `line`
+    /// saying what was `expected`.
+    pub fn captures<'a>(&self, text: &str, line: &'a str, expected: &str) -> Result<Captures<'a>> {
+        self.regex()
+            .captures(line)
+            .ok_or_else(|| Error::at(text, line, format!("expected `{}`", expected)))
+    }
+
+    fn regex(&self) -> &Regex {
+        self.regex
+            .get_or_init(|| Regex::new(self.source).expect("invalid pattern"))
+    }
+}
+impl Deref for Pattern {
+    type Target = Regex;
+
+    fn deref(&self) -> &Regex {
+        self.regex()
+    }
+}
+
+static INTEGER: Pattern = Pattern::new(r"^-?\d+$");
+
+/// The integers in `line`, separated by whitespace. Any other token is an
+/// error at that token.
+pub fn integers<T: FromStr>(text: &str, line: &str) -> Result<Vec<T>> {
+    line.split_whitespace()
+        .map(|token| match INTEGER.is_match(token) {
+            // Digits that still don't parse are out of range for `T`.
+            true => utils::parse_number(text, token),
+            false => Err(Error::at(text, token, "expected an integer")),
+        })
+        .collect()
+}
+
+/// Splits a `key: value` line into its key and value, without the spaces
+/// around them. `expected` describes the line for the error if there is no `:`.
+pub fn key_value<'a>(text: &str, line: &'a str, expected: &str) -> Result<(&'a str, &'a str)> {
+    let (key, value) = line
+        .split_once(':')
+        .ok_or_else(|| Error::at(text, line, format!("expected `{}`", expected)))?;
+    Ok((key.trim(), value.trim()))
+}
+
+/// The number in a `<name> <number>` label such as `Card 12`, which may have
+/// any amount of space between the two.
+pub fn labelled_number<T: FromStr>(text: &str, label: &str, name: &str) -> Result<T> {
+    match label.strip_prefix(name) {
+        Some(number) if number.starts_with(char::is_whitespace) => {
+            utils::parse_number(text, number.trim_start())
+        }
+        _ => Err(Error::at(
+            text,
+            label,
+            format!("expected `{} <number>`", name),
+        )),
+    }
+}
+
+/// Splits a `key: list | list` line into its key and the integers in each
+/// list, with lists separated by `separator`.
+pub fn key_lists<'a, T: FromStr>(
+    text: &str,
+    line: &'a str,
+    separator: char,
+) -> Result<(&'a str, Vec<Vec<T>>)> {
+    let expected = format!("<key>: <numbers> {} <numbers>", separator);
+    let (key, value) = key_value(text, line, &expected)?;
+    let lists = value
+        .split(separator)
+        .map(|list| integers(text, list))
+        .collect::<Result<Vec<Vec<T>>>>()?;
+    Ok((key, lists))
+}
+
+/// The blocks of `text` that are separated by blank lines, without the line
+/// breaks around them.
+pub fn blocks(text: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut block: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        match (content.trim().is_empty(), block) {
+            (true, Some((start, end))) => {
+                blocks.push(&text[start..end]);
+                block = None;
+            }
+            (true, None) => {}
+            (false, Some((start, _))) => block = Some((start, offset + content.len())),
+            (false, None) => block = Some((offset, offset + content.len())),
+        }
+        offset += line.len();
+    }
+    if let Some((start, end)) = block {
+        blocks.push(&text[start..end]);
+    }
+    blocks
+}
+
+/// A block that starts with a `label: value` line, such as
+/// `seeds: 79 14 55 13` or `seed-to-soil map:` followed by more lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub label: &'a str,
+    /// The rest of the first line, which may be empty.
+    pub value: &'a str,
+    /// The lines after the first.
+    pub lines: Vec<&'a str>,
+}
+
+/// Splits `block` into a labelled record.
+pub fn record<'a>(text: &str, block: &'a str) -> Result<Record<'a>> {
+    let mut lines = block.lines();
+    let first = lines.next().unwrap_or(block);
+    let (label, value) = key_value(text, first, "<label>: <value>")?;
+    Ok(Record {
+        label,
+        value,
+        lines: lines.collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where `result` failed, as `(line, column, message)`.
+    fn failure<T: std::fmt::Debug>(result: Result<T>) -> (usize, usize, String) {
+        match result {
+            Err(Error::Parse {
+                line,
+                column,
+                message,
+                ..
+            }) => (line, column, message),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn integers_split_on_whitespace() {
+        let text = "1 -2\t 30  \n";
+        assert_eq!(integers::<i32>(text, text).unwrap(), [1, -2, 30]);
+        assert_eq!(integers::<u8>("", "").unwrap(), []);
+    }
+
+    #[test]
+    fn integers_reject_other_tokens() {
+        let text = "seeds: 1 2\n3 x 4";
+        let second = text.lines().nth(1).unwrap();
+        assert_eq!(
+            failure(integers::<i32>(text, second)),
+            (2, 3, "expected an integer".to_string())
+        );
+        assert_eq!(failure(integers::<i32>(text, text)).1, 1);
+        assert_eq!(failure(integers::<i32>("1 2,3", "1 2,3")).1, 3);
+        assert_eq!(failure(integers::<i32>("4-5", "4-5")).1, 1);
+        assert_eq!(
+            failure(integers::<u8>("7 300", "7 300")),
+            (1, 3, "invalid number `300`".to_string())
+        );
+        assert_eq!(
+            failure(integers::<usize>("-1", "-1")),
+            (1, 1, "invalid number `-1`".to_string())
+        );
+    }
+
+    #[test]
+    fn key_values_and_labels() {
+        let text = "Card  12:  41 48 | 83 86 6\nno colon";
+        let first = text.lines().next().unwrap();
+        let (key, value) = key_value(text, first, "<key>: <value>").unwrap();
+        assert_eq!((key, value), ("Card  12", "41 48 | 83 86 6"));
+        assert_eq!(labelled_number::<u32>(text, key, "Card").unwrap(), 12);
+        assert_eq!(
+            failure(labelled_number::<u32>(text, key, "Game")),
+            (1, 1, "expected `Game <number>`".to_string())
+        );
+        assert_eq!(
+            failure(labelled_number::<u32>("Card12", "Card12", "Card")).1,
+            1
+        );
+
+        let second = text.lines().nth(1).unwrap();
+        assert_eq!(
+            failure(key_value(text, second, "<key>: <value>")),
+            (2, 1, "expected `<key>: <value>`".to_string())
+        );
+
+        let (key, lists) = key_lists::<u32>(text, first, '|').unwrap();
+        assert_eq!(key, "Card  12");
+        assert_eq!(lists, [Vec::from([41, 48]), Vec::from([83, 86, 6])]);
+        let bad = "Card 1: 1 2 | 3 x";
+        assert_eq!(failure(key_lists::<u32>(bad, bad, '|')).1, 17);
+    }
+
+    #[test]
+    fn blocks_between_blank_lines() {
+        let text = "\na\nb\n\n  \nc\r\n\r\nd\n\n";
+        assert_eq!(blocks(text), ["a\nb", "c", "d"]);
+        assert_eq!(blocks("only"), ["only"]);
+        assert!(blocks("\n \n").is_empty());
+    }
+
+    #[test]
+    fn records_with_labels() {
+        let text = "seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n52 50 48";
+        let sections = blocks(text);
+        assert_eq!(
+            record(text, sections[0]).unwrap(),
+            Record {
+                label: "seeds",
+                value: "79 14",
+                lines: Vec::new(),
+            }
+        );
+        assert_eq!(
+            record(text, sections[1]).unwrap(),
+            Record {
+                label: "seed-to-soil map",
+                value: "",
+                lines: Vec::from(["50 98 2", "52 50 48"]),
+            }
+        );
+        assert_eq!(failure(record(text, &text[14..19])).0, 3);
+    }
+
+    #[test]
+    fn patterns_compile_once_and_report_what_they_expected() {
+        static GAME: Pattern = Pattern::new(r"^Game (\d+)$");
+        let text = "Game 7\nGame seven";
+        let mut lines = text.lines();
+        let captures = GAME
+            .captures(text, lines.next().unwrap(), "Game <id>")
+            .unwrap();
+        assert_eq!(&captures[1], "7");
+        assert_eq!(
+            failure(GAME.captures(text, lines.next().unwrap(), "Game <id>")),
+            (2, 1, "expected `Game <id>`".to_string())
+        );
+        assert!(std::ptr::eq(GAME.regex(), GAME.deref()));
+    }
+}
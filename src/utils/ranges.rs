@@ -0,0 +1,490 @@
+//! Half-open intervals of numbers and sets of them, for puzzles that work on
+//! spans far too big to walk one value at a time.
+
+use std::fmt;
+use std::ops::{Add, Sub};
+
+/// The values from `start` up to, but not including, `end`. An interval whose
+/// end is not after its start is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+impl<T: Copy + Ord> Interval<T> {
+    pub const fn new(start: T, end: T) -> Interval<T> {
+        Interval { start, end }
+    }
+
+    /// The `len` values from `start` on.
+    pub fn with_len(start: T, len: T) -> Interval<T>
+    where
+        T: Add<Output = T>,
+    {
+        Interval::new(start, start + len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    /// How many values are in the interval, or `T::default()` (zero) if none.
+    pub fn len(&self) -> T
+    where
+        T: Sub<Output = T> + Default,
+    {
+        match self.is_empty() {
+            true => T::default(),
+            false => self.end - self.start,
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value < self.end
+    }
+
+    /// Whether every value of `other` is also in this interval. The empty
+    /// interval is in every interval.
+    pub fn contains_interval(&self, other: &Interval<T>) -> bool {
+        other.is_empty() || (self.start <= other.start && other.end <= self.end)
+    }
+
+    /// Whether the two intervals have a value in common.
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.intersect(other).is_some()
+    }
+
+    /// The values in both intervals, if there are any.
+    pub fn intersect(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        let intersection = Interval::new(self.start.max(other.start), self.end.min(other.end));
+        (!intersection.is_empty()).then_some(intersection)
+    }
+}
+impl<T: fmt::Display> fmt::Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {})", self.start, self.end)
+    }
+}
+
+/// A set of values, kept as sorted intervals that are neither empty nor
+/// overlapping nor touching, so that each set has exactly one form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RangeSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+impl<T: Copy + Ord> RangeSet<T> {
+    pub const fn new() -> RangeSet<T> {
+        RangeSet {
+            intervals: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The set's intervals, in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Interval<T>> {
+        self.intervals.iter()
+    }
+
+    /// The smallest value in the set.
+    pub fn min(&self) -> Option<T> {
+        self.intervals.first().map(|i| i.start)
+    }
+
+    /// The smallest interval holding the whole set.
+    pub fn bounds(&self) -> Option<Interval<T>> {
+        match (self.intervals.first(), self.intervals.last()) {
+            (Some(first), Some(last)) => Some(Interval::new(first.start, last.end)),
+            _ => None,
+        }
+    }
+
+    /// How many values are in the set.
+    pub fn len(&self) -> T
+    where
+        T: Sub<Output = T> + Add<Output = T> + Default,
+    {
+        self.intervals
+            .iter()
+            .fold(T::default(), |total, i| total + i.len())
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.containing(value).is_some()
+    }
+
+    /// Whether every value of `interval` is in the set.
+    pub fn contains_interval(&self, interval: &Interval<T>) -> bool {
+        interval.is_empty()
+            || self
+                .containing(interval.start)
+                .is_some_and(|i| i.contains_interval(interval))
+    }
+
+    /// The set's interval that `value` is in.
+    fn containing(&self, value: T) -> Option<&Interval<T>> {
+        let after = self.intervals.partition_point(|i| i.start <= value);
+        after
+            .checked_sub(1)
+            .map(|i| &self.intervals[i])
+            .filter(|i| i.contains(value))
+    }
+
+    /// Adds the values of `interval` to the set.
+    pub fn insert(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        // Everything from the first interval that touches `interval` to the
+        // last one is merged into it.
+        let first = self.intervals.partition_point(|i| i.end < interval.start);
+        let last = self.intervals.partition_point(|i| i.start <= interval.end);
+        let merged = match first < last {
+            true => Interval::new(
+                self.intervals[first].start.min(interval.start),
+                self.intervals[last - 1].end.max(interval.end),
+            ),
+            false => interval,
+        };
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    /// The values in either set.
+    pub fn union(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut union = self.clone();
+        for &interval in &other.intervals {
+            union.insert(interval);
+        }
+        union
+    }
+
+    /// The values in both sets.
+    pub fn intersection(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut intervals = Vec::new();
+        let (mut a, mut b) = (
+            self.intervals.iter().peekable(),
+            other.intervals.iter().peekable(),
+        );
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            intervals.extend(x.intersect(y));
+            // Whichever ends first can't meet anything further along the
+            // other set.
+            match x.end <= y.end {
+                true => a.next(),
+                false => b.next(),
+            };
+        }
+        // Intersections of disjoint, non-touching intervals can't touch.
+        RangeSet { intervals }
+    }
+
+    /// The values in this set but not in `other`.
+    pub fn difference(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        match self.bounds() {
+            Some(bounds) => self.intersection(&other.complement(bounds)),
+            None => RangeSet::new(),
+        }
+    }
+
+    /// The values in `bounds` that are not in the set.
+    pub fn complement(&self, bounds: Interval<T>) -> RangeSet<T> {
+        let mut intervals = Vec::new();
+        let mut start = bounds.start;
+        for interval in &self.intervals {
+            if interval.start >= bounds.end {
+                break;
+            }
+            let gap = Interval::new(start, interval.start.min(bounds.end));
+            if !gap.is_empty() {
+                intervals.push(gap);
+            }
+            start = start.max(interval.end);
+        }
+        let rest = Interval::new(start, bounds.end);
+        if !rest.is_empty() {
+            intervals.push(rest);
+        }
+        RangeSet { intervals }
+    }
+}
+impl<T: Copy + Ord> Default for RangeSet<T> {
+    fn default() -> RangeSet<T> {
+        RangeSet::new()
+    }
+}
+impl<T: Copy + Ord> From<Interval<T>> for RangeSet<T> {
+    fn from(interval: Interval<T>) -> RangeSet<T> {
+        let mut set = RangeSet::new();
+        set.insert(interval);
+        set
+    }
+}
+impl<T: Copy + Ord> FromIterator<Interval<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(intervals: I) -> RangeSet<T> {
+        let mut set = RangeSet::new();
+        set.extend(intervals);
+        set
+    }
+}
+impl<T: Copy + Ord> Extend<Interval<T>> for RangeSet<T> {
+    fn extend<I: IntoIterator<Item = Interval<T>>>(&mut self, intervals: I) {
+        for interval in intervals {
+            self.insert(interval);
+        }
+    }
+}
+impl<T: fmt::Display> fmt::Display for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, interval) in self.intervals.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", interval)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(intervals: &[(u32, u32)]) -> RangeSet<u32> {
+        intervals
+            .iter()
+            .map(|&(start, end)| Interval::new(start, end))
+            .collect()
+    }
+
+    fn intervals(set: &RangeSet<u32>) -> Vec<(u32, u32)> {
+        set.iter().map(|i| (i.start, i.end)).collect()
+    }
+
+    #[test]
+    fn interval_basics() {
+        let interval = Interval::new(3, 7);
+        assert_eq!(interval.len(), 4);
+        assert!(interval.contains(3) && interval.contains(6));
+        assert!(!interval.contains(7) && !interval.contains(2));
+        assert_eq!(Interval::with_len(3, 4), interval);
+
+        let empty = Interval::new(5, 5);
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+        assert_eq!(Interval::new(7, 3).len(), 0);
+        assert!(interval.contains_interval(&empty));
+        assert!(interval.contains_interval(&Interval::new(100, 50)));
+    }
+
+    #[test]
+    fn interval_intersect() {
+        let interval = Interval::new(3, 7);
+        assert_eq!(
+            interval.intersect(&Interval::new(5, 10)),
+            Some(Interval::new(5, 7))
+        );
+        assert_eq!(
+            interval.intersect(&Interval::new(0, 4)),
+            Some(Interval::new(3, 4))
+        );
+        assert_eq!(
+            interval.intersect(&Interval::new(4, 5)),
+            Some(Interval::new(4, 5))
+        );
+        assert_eq!(interval.intersect(&Interval::new(0, 10)), Some(interval));
+        // Touching intervals share no value.
+        assert_eq!(interval.intersect(&Interval::new(7, 9)), None);
+        assert!(!interval.overlaps(&Interval::new(0, 3)));
+        assert!(interval.overlaps(&Interval::new(0, 4)));
+    }
+
+    #[test]
+    fn insert_merges_overlapping_and_touching() {
+        let mut set = RangeSet::new();
+        set.insert(Interval::new(10, 20));
+        set.insert(Interval::new(30, 40));
+        assert_eq!(intervals(&set), [(10, 20), (30, 40)]);
+
+        set.insert(Interval::new(20, 25));
+        assert_eq!(intervals(&set), [(10, 25), (30, 40)]);
+        set.insert(Interval::new(5, 10));
+        assert_eq!(intervals(&set), [(5, 25), (30, 40)]);
+        set.insert(Interval::new(35, 45));
+        assert_eq!(intervals(&set), [(5, 25), (30, 45)]);
+        set.insert(Interval::new(0, 1));
+        assert_eq!(intervals(&set), [(0, 1), (5, 25), (30, 45)]);
+        set.insert(Interval::new(12, 13));
+        assert_eq!(intervals(&set), [(0, 1), (5, 25), (30, 45)]);
+        set.insert(Interval::new(1, 30));
+        assert_eq!(intervals(&set), [(0, 45)]);
+    }
+
+    #[test]
+    fn insert_ignores_empty_intervals() {
+        let mut set = set(&[(0, 5)]);
+        set.insert(Interval::new(10, 10));
+        set.insert(Interval::new(20, 15));
+        assert_eq!(intervals(&set), [(0, 5)]);
+        assert!(RangeSet::from(Interval::new(3, 3)).is_empty());
+    }
+
+    #[test]
+    fn union() {
+        let a = set(&[(0, 5), (10, 15)]);
+        let b = set(&[(5, 7), (12, 20), (30, 31)]);
+        assert_eq!(intervals(&a.union(&b)), [(0, 7), (10, 20), (30, 31)]);
+        assert_eq!(a.union(&RangeSet::new()), a);
+    }
+
+    #[test]
+    fn intersection() {
+        let a = set(&[(0, 5), (10, 15), (20, 30)]);
+        let b = set(&[(3, 12), (14, 22), (30, 40)]);
+        assert_eq!(
+            intervals(&a.intersection(&b)),
+            [(3, 5), (10, 12), (14, 15), (20, 22)]
+        );
+        assert_eq!(a.intersection(&b), b.intersection(&a));
+        assert!(a.intersection(&set(&[(5, 10), (15, 20)])).is_empty());
+        assert!(a.intersection(&RangeSet::new()).is_empty());
+    }
+
+    #[test]
+    fn difference() {
+        let a = set(&[(0, 10), (20, 30)]);
+        assert_eq!(
+            intervals(&a.difference(&set(&[(3, 5)]))),
+            [(0, 3), (5, 10), (20, 30)]
+        );
+        assert_eq!(
+            intervals(&a.difference(&set(&[(5, 25)]))),
+            [(0, 5), (25, 30)]
+        );
+        assert_eq!(
+            intervals(&a.difference(&set(&[(10, 20)]))),
+            [(0, 10), (20, 30)]
+        );
+        assert!(a.difference(&set(&[(0, 30)])).is_empty());
+        assert_eq!(a.difference(&RangeSet::new()), a);
+        assert!(RangeSet::new().difference(&a).is_empty());
+    }
+
+    #[test]
+    fn complement_within_bounds() {
+        let a = set(&[(5, 10), (20, 25)]);
+        assert_eq!(
+            intervals(&a.complement(Interval::new(0, 30))),
+            [(0, 5), (10, 20), (25, 30)]
+        );
+        // Intervals partly outside the bounds.
+        assert_eq!(intervals(&a.complement(Interval::new(7, 22))), [(10, 20)]);
+        // Intervals wholly outside the bounds.
+        assert_eq!(intervals(&a.complement(Interval::new(11, 19))), [(11, 19)]);
+        assert_eq!(intervals(&a.complement(Interval::new(30, 40))), [(30, 40)]);
+        assert_eq!(intervals(&a.complement(Interval::new(0, 3))), [(0, 3)]);
+        // Bounds wholly inside the set.
+        assert!(a.complement(Interval::new(6, 9)).is_empty());
+        assert!(a.complement(Interval::new(5, 5)).is_empty());
+        assert_eq!(
+            intervals(&RangeSet::new().complement(Interval::new(1, 2))),
+            [(1, 2)]
+        );
+    }
+
+    #[test]
+    fn len_and_bounds() {
+        let a = set(&[(0, 5), (10, 12), (4, 6)]);
+        assert_eq!(a.len(), 8);
+        assert_eq!(a.min(), Some(0));
+        assert_eq!(a.bounds(), Some(Interval::new(0, 12)));
+
+        let empty = RangeSet::<u32>::new();
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.min(), None);
+        assert_eq!(empty.bounds(), None);
+    }
+
+    #[test]
+    fn containment() {
+        let a = set(&[(0, 5), (10, 15)]);
+        assert!(a.contains(0) && a.contains(4) && a.contains(10));
+        assert!(!a.contains(5) && !a.contains(9) && !a.contains(15));
+        assert!(a.contains_interval(&Interval::new(1, 5)));
+        assert!(a.contains_interval(&Interval::new(10, 15)));
+        assert!(!a.contains_interval(&Interval::new(4, 11)));
+        assert!(!a.contains_interval(&Interval::new(5, 10)));
+        assert!(a.contains_interval(&Interval::new(7, 7)));
+
+        let empty = RangeSet::new();
+        assert!(!empty.contains(0));
+        assert!(!empty.contains_interval(&Interval::new(0, 1)));
+        assert!(empty.contains_interval(&Interval::new(1, 0)));
+    }
+
+    /// Checks every operation against the same operation on plain sets of
+    /// small numbers, for many sets from a fixed pseudo-random sequence.
+    #[test]
+    fn agrees_with_plain_sets() {
+        use std::collections::BTreeSet;
+
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = |below: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % below) as u32
+        };
+        let random_set = |next: &mut dyn FnMut(u64) -> u32| {
+            let mut set = RangeSet::new();
+            let mut values = BTreeSet::new();
+            for _ in 0..next(5) {
+                let interval = Interval::with_len(next(40), next(8));
+                set.insert(interval);
+                values.extend(interval.start..interval.end);
+            }
+            (set, values)
+        };
+        let values = |set: &RangeSet<u32>| {
+            for pair in set.intervals.windows(2) {
+                assert!(pair[0].end < pair[1].start, "not normalized: {}", set);
+            }
+            assert!(set.iter().all(|i| !i.is_empty()), "not normalized: {}", set);
+            set.iter()
+                .flat_map(|i| i.start..i.end)
+                .collect::<BTreeSet<u32>>()
+        };
+
+        for _ in 0..2000 {
+            let (a, a_values) = random_set(&mut next);
+            let (b, b_values) = random_set(&mut next);
+            assert_eq!(values(&a), a_values);
+            assert_eq!(values(&a.union(&b)), &a_values | &b_values);
+            assert_eq!(values(&a.intersection(&b)), &a_values & &b_values);
+            assert_eq!(values(&a.difference(&b)), &a_values - &b_values);
+            assert_eq!(a.len() as usize, a_values.len());
+
+            let (start, end) = (next(45), next(50));
+            let bounds = Interval::new(start, end);
+            let outside = (start..end)
+                .filter(|v| !a_values.contains(v))
+                .collect::<BTreeSet<u32>>();
+            assert_eq!(values(&a.complement(bounds)), outside);
+            assert_eq!(
+                a.contains_interval(&bounds),
+                (start..end).all(|v| a_values.contains(&v))
+            );
+            for value in 0..50 {
+                assert_eq!(a.contains(value), a_values.contains(&value));
+            }
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(set(&[(1, 3), (5, 6)]).to_string(), "{[1, 3), [5, 6)}");
+        assert_eq!(RangeSet::<u32>::new().to_string(), "{}");
+    }
+}
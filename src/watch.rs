@@ -0,0 +1,239 @@
+use crate::answers::Answers;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::isolate;
+use crate::manifest;
+use crate::runner::{Record, Status};
+use crate::scaffold;
+use crate::solutions::Part;
+use crate::utils::{self, Dataset};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Notices changes to a day's solution, its data files and the known answers
+/// by polling their modification times.
+pub struct Watcher {
+    root: PathBuf,
+    day: u32,
+    /// Directories holding the day's examples and real input.
+    data_dirs: Vec<PathBuf>,
+    fixed: Vec<PathBuf>,
+    seen: BTreeMap<PathBuf, Option<SystemTime>>,
+}
+impl Watcher {
+    pub fn new(root: &Path, config: &Config, day: u32) -> Watcher {
+        let mut fixed = Vec::from([scaffold::source_path(root, day), config.answers_path()]);
+        fixed.extend(config.user_answers_path());
+        Watcher {
+            root: root.to_path_buf(),
+            day,
+            data_dirs: config.data_dirs(),
+            fixed,
+            seen: BTreeMap::new(),
+        }
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = self.fixed.clone();
+        for dir in &self.data_dirs {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            files.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        path.file_name()
+                            .and_then(|name| name.to_str())
+                            .is_some_and(|name| utils::is_day_file(name, self.day))
+                    }),
+            );
+        }
+        files
+    }
+
+    /// The files that were created, changed or removed since the last call,
+    /// relative to the repository root where possible. Every file counts as
+    /// changed on the first call.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let now = self
+            .files()
+            .into_iter()
+            .map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (path, modified)
+            })
+            .collect::<BTreeMap<PathBuf, Option<SystemTime>>>();
+        let mut changed = now
+            .iter()
+            .filter(|(path, modified)| self.seen.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<PathBuf>>();
+        changed.extend(self.seen.keys().filter(|p| !now.contains_key(*p)).cloned());
+        self.seen = now;
+        changed
+            .into_iter()
+            .map(|path| match path.strip_prefix(&self.root) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => path,
+            })
+            .collect()
+    }
+}
+
+/// The records for one day, keyed by dataset name and part.
+pub type Results = BTreeMap<(String, Part), Record>;
+
+/// Rebuilds the binary and runs `day` against its examples and real input,
+/// each part in a child process of the new build, so that edits to the
+/// solution take effect. `program` is where cargo puts the binary, and
+/// `answers` the known answers to check against. Returns `None` if the build
+/// failed; cargo's errors are shown as they happen.
+pub fn run_day(
+    root: &Path,
+    program: &Path,
+    config: &Config,
+    answers: &Answers,
+    day: u32,
+) -> Result<Option<Results>> {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(&cargo);
+    command.current_dir(root).args(["build", "--quiet"]);
+    if !cfg!(debug_assertions) {
+        command.arg("--release");
+    }
+    let built = command
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| Error::io(&cargo, e))?;
+    if !built.success() {
+        return Ok(None);
+    }
+
+    let mut results = Results::new();
+    for part in [Part::One, Part::Two] {
+        let mut datasets = answers.examples(day, part);
+        datasets.push(Dataset::Real);
+        for dataset in datasets {
+            let record = Record::new(day, Some(dataset.clone()), part, answers);
+            let (solved, parse_time, solve_time) =
+                match manifest::read_checked(config.input_path(day, &dataset)) {
+                    Ok(input) => isolate::solve_with(program, day, part, &input, &config.limits),
+                    Err(e) => (
+                        Err(Status::Error(e.to_string())),
+                        Duration::ZERO,
+                        Duration::ZERO,
+                    ),
+                };
+            let record = Record {
+                parse_time,
+                solve_time,
+                ..record
+            };
+            results.insert((dataset.to_string(), part), record.checked(solved, answers));
+        }
+    }
+    Ok(Some(results))
+}
+
+/// Describes each result, with how its answer changed since `previous` and how
+/// it compares to the known answer.
+pub fn describe(previous: Option<&Results>, results: &Results) -> Vec<String> {
+    let shown = |record: &Record| match &record.answer {
+        Some(answer) => answer.to_string().replace('\n', "\\n"),
+        None => "-".to_string(),
+    };
+    results
+        .iter()
+        .map(|((dataset, part), record)| {
+            let change = match previous.and_then(|p| p.get(&(dataset.clone(), *part))) {
+                Some(before) if before.answer != record.answer => {
+                    format!(" (was {})", shown(before))
+                }
+                _ => String::new(),
+            };
+            let status = match (&record.status, record.message()) {
+                (Status::Fail, _) => format!(
+                    "FAIL: expected {}",
+                    record.expected.as_deref().unwrap_or("-")
+                ),
+                (status, Some(message)) => format!("{}: {}", status, message),
+                (status, None) => status.to_string(),
+            };
+            format!(
+                "{:<12} part {}: {}{} [{}]",
+                dataset,
+                part.number(),
+                shown(record),
+                change,
+                status
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Answer;
+
+    fn results(
+        records: impl IntoIterator<Item = (Dataset, Part, std::result::Result<i64, Status>)>,
+    ) -> Results {
+        let answers = Answers::parse("6 1 test 288\n6 2 test 71503\n").unwrap();
+        records
+            .into_iter()
+            .map(|(dataset, part, solved)| {
+                let record = Record::new(6, Some(dataset.clone()), part, &answers);
+                let solved = solved.map(Answer::Number);
+                (
+                    (dataset.to_string(), part),
+                    record.checked(solved, &answers),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn describes_changes_and_checks() {
+        let example = Dataset::default_example;
+        let before = results([
+            (example(), Part::One, Ok(288)),
+            (example(), Part::Two, Ok(1)),
+            (Dataset::Real, Part::One, Ok(5)),
+        ]);
+        assert_eq!(
+            describe(None, &before),
+            [
+                "real         part 1: 5 [unknown]",
+                "test         part 1: 288 [pass]",
+                "test         part 2: 1 [FAIL: expected 71503]",
+            ]
+        );
+
+        let after = results([
+            (
+                example(),
+                Part::One,
+                Err(Status::Panicked("oops".to_string())),
+            ),
+            (example(), Part::Two, Ok(71503)),
+            (Dataset::Real, Part::One, Ok(5)),
+        ]);
+        assert_eq!(
+            describe(Some(&before), &after),
+            [
+                "real         part 1: 5 [unknown]",
+                "test         part 1: - (was 288) [PANIC: oops]",
+                "test         part 2: 71503 (was 1) [pass]",
+            ]
+        );
+    }
+}
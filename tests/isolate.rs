@@ -0,0 +1,71 @@
+//! Parts run in child processes of the built binary, as they are with limits.
+
+use advent2023::isolate;
+use advent2023::runner::{Limits, Status};
+use advent2023::solutions::{Answer, Part};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+fn program() -> &'static Path {
+    Path::new(env!("CARGO_BIN_EXE_advent2023"))
+}
+
+const EXAMPLE: &str = "LLR\n\nAAA = (BBB, BBB)\nBBB = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)\n";
+
+/// Never reaches `ZZZ`, so part one runs until it is stopped.
+const ENDLESS: &str = "L\n\nAAA = (BBB, BBB)\nBBB = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)\n";
+
+#[test]
+fn answers_come_back_from_the_child() {
+    let limits = Limits {
+        timeout: Some(Duration::from_secs(30)),
+        memory: None,
+    };
+    let (answer, _, _) = isolate::solve_with(program(), 8, Part::One, EXAMPLE, &limits);
+    assert_eq!(answer, Ok(Answer::Number(6)));
+
+    let (failed, _, solve_time) =
+        isolate::solve_with(program(), 8, Part::One, "LR\n\nAAA = (BBB, ZZZ)\n", &limits);
+    assert_eq!(
+        failed,
+        Err(Status::Error(
+            "day 8, line 3, column 8: undefined node `BBB`".to_string()
+        ))
+    );
+    assert_eq!(solve_time, Duration::ZERO);
+}
+
+#[test]
+fn parts_that_run_too_long_are_stopped() {
+    let limits = Limits {
+        timeout: Some(Duration::from_millis(300)),
+        memory: None,
+    };
+    let start = Instant::now();
+    let (answer, parse_time, solve_time) =
+        isolate::solve_with(program(), 8, Part::One, ENDLESS, &limits);
+    assert!(matches!(answer, Err(Status::Timeout(_))), "{:?}", answer);
+    assert!(parse_time < Duration::from_millis(300));
+    assert!(solve_time >= Duration::from_millis(300));
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[cfg(unix)]
+#[test]
+fn parts_that_use_too_much_memory_are_stopped() {
+    let limits = Limits {
+        timeout: Some(Duration::from_secs(30)),
+        memory: Some(1024 * 1024),
+    };
+    // Enough nodes that parsing them needs more than a MiB, and then enough
+    // that even reading them does.
+    for nodes in [10_000, 60_000] {
+        let mut input = String::from("L\n\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n");
+        for i in 0..nodes {
+            input.push_str(&format!("{:03} = (ZZZ, ZZZ)\n", i % 1000));
+        }
+        let (answer, _, _) = isolate::solve_with(program(), 8, Part::One, &input, &limits);
+        let expected = Status::OutOfMemory("tried to use more than 1 MiB".to_string());
+        assert_eq!(answer, Err(expected), "with {} nodes", nodes);
+    }
+}
@@ -0,0 +1,40 @@
+//! Input piped into a run of one day stands in for its data file.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+const EXAMPLE: &str = "Time:      7  15   30\nDistance:  9  40  200\n";
+
+fn run_day_six(stdin: Option<&str>) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_advent2023"));
+    command
+        .args(["--day", "6", "--part", "1", "--data-dir"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/data"))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let Some(input) = stdin else {
+        return command.stdin(Stdio::null()).output().unwrap();
+    };
+    let mut child = command.stdin(Stdio::piped()).spawn().unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn piped_input_is_read_with_a_note() {
+    let output = run_day_six(Some(EXAMPLE));
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Part one: 288"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("reading day 6's input from stdin"));
+}
+
+#[test]
+fn no_note_without_piped_input() {
+    let output = run_day_six(None);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("stdin"));
+}